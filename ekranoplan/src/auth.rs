@@ -14,11 +14,14 @@
    limitations under the License.
 */
 
+use std::{env, net::SocketAddr, time::Duration};
+
+use argon2::{Argon2, PasswordVerifier};
 use axum::http::HeaderMap;
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use models::{Account, Actor};
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool, types::chrono};
 
 use crate::Error;
 
@@ -39,7 +42,16 @@ impl Claims {
     }
 
     pub fn from_token(token: &str, key: &DecodingKey) -> Result<Self, Error> {
-        Ok(decode::<Self>(token, key, &Validation::new(jsonwebtoken::Algorithm::HS256))?.claims)
+        let claims =
+            decode::<Self>(token, key, &Validation::new(jsonwebtoken::Algorithm::HS256))?.claims;
+
+        // tokens used to be issued with millisecond timestamps, which put
+        // their expiry thousands of years out
+        if claims.iat > chrono::Utc::now().timestamp() as usize + 60 {
+            return Err(Error::BadToken);
+        }
+
+        Ok(claims)
     }
 
     pub fn from_token_map(map: &HeaderMap, key: &DecodingKey) -> Result<Self, Error> {
@@ -51,7 +63,17 @@ impl Claims {
     }
 }
 
-pub async fn get_user(map: &HeaderMap, key: &str, db: &PgPool) -> Result<(Actor, Account), Error> {
+static ACCESS_TOKEN_LIFETIME: Duration = Duration::from_secs(15 * 60);
+
+// only bother writing last_seen once a minute
+static LAST_SEEN_INTERVAL: i64 = 60_000;
+
+/// Like [`get_user`], but also returns the id of the session the request was made with.
+pub async fn get_session(
+    map: &HeaderMap,
+    key: &str,
+    db: &PgPool,
+) -> Result<(String, Actor, Account), Error> {
     let claims = Claims::from_token_map(map, &DecodingKey::from_secret(key.as_bytes()))?;
 
     if let Some(account) = sqlx::query_as!(
@@ -62,7 +84,18 @@ pub async fn get_user(map: &HeaderMap, key: &str, db: &PgPool) -> Result<(Actor,
     .fetch_optional(db)
     .await?
     {
+        let time = chrono::Utc::now().timestamp_millis();
+        sqlx::query!(
+            "UPDATE sessions SET last_seen = $1 WHERE id = $2 AND last_seen < $3;",
+            time,
+            claims.sub,
+            time - LAST_SEEN_INTERVAL
+        )
+        .execute(db)
+        .await?;
+
         Ok((
+            claims.sub,
             sqlx::query_as!(Actor, "SELECT * FROM actors WHERE id = $1;", &account.id)
                 .fetch_one(db)
                 .await?,
@@ -72,3 +105,95 @@ pub async fn get_user(map: &HeaderMap, key: &str, db: &PgPool) -> Result<(Actor,
         Err(Error::ExpiredSession)
     }
 }
+
+pub async fn get_user(map: &HeaderMap, key: &str, db: &PgPool) -> Result<(Actor, Account), Error> {
+    let (_, actor, account) = get_session(map, key, db).await?;
+    Ok((actor, account))
+}
+
+/// Whether `password` is the account's. A stored hash which can't be parsed
+/// is our fault rather than the client's, so it fails as an internal error.
+pub fn verify_password(account: &Account, password: &str) -> Result<bool, Error> {
+    let hash =
+        argon2::PasswordHash::new(&account.password).map_err(|_| Error::FailedPasswordHash)?;
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .is_ok())
+}
+
+/// Creates a session for `user_id`, recording where the request came from.
+pub async fn create_session(
+    conn: &mut PgConnection,
+    user_id: &str,
+    map: &HeaderMap,
+    addr: SocketAddr,
+) -> Result<String, Error> {
+    let session_id = nanoid::nanoid!();
+    let time = chrono::Utc::now().timestamp_millis();
+
+    let user_agent = map
+        .get("user-agent")
+        .and_then(|ua| ua.to_str().ok())
+        .map(|ua| ua.to_string());
+    // anyone can send X-Forwarded-For, so it's only read when TRUST_PROXY
+    // says a proxy sits in front of us, and then only the address that
+    // proxy appended last
+    let trust_proxy = env::var("TRUST_PROXY")
+        .unwrap_or("false".to_string())
+        .parse::<bool>()
+        .unwrap_or(false);
+    let forwarded = if trust_proxy {
+        map.get("x-forwarded-for")
+            .and_then(|ip| ip.to_str().ok())
+            .and_then(|ips| ips.rsplit(',').next())
+            .map(|ip| ip.trim().to_string())
+            .filter(|ip| !ip.is_empty())
+    } else {
+        None
+    };
+    let ip = forwarded.unwrap_or_else(|| addr.ip().to_string());
+
+    sqlx::query!(
+        "INSERT INTO sessions (id, user_id, created_at, last_seen, user_agent, ip) VALUES ($1, $2, $3, $3, $4, $5);",
+        &session_id,
+        user_id,
+        time,
+        user_agent,
+        ip
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(session_id)
+}
+
+/// Issues a short-lived access token for `session_id` along with the
+/// refresh token to get the next one with.
+pub async fn issue_tokens(
+    conn: &mut PgConnection,
+    session_id: &str,
+    user_id: &str,
+    key: &str,
+) -> Result<(String, String), Error> {
+    let now = chrono::Utc::now();
+
+    let token = Claims {
+        sub: session_id.to_string(),
+        iat: now.timestamp() as usize,
+        exp: now.timestamp() as usize + ACCESS_TOKEN_LIFETIME.as_secs() as usize,
+    }
+    .make_token(&EncodingKey::from_secret(key.as_bytes()))?;
+
+    let refresh_token = nanoid::nanoid!(64);
+    sqlx::query!(
+        "INSERT INTO refresh_tokens (token_hash, session_id, user_id, expires_at) VALUES ($1, $2, $3, $4);",
+        blake3::hash(refresh_token.as_bytes()).to_string(),
+        session_id,
+        user_id,
+        now.timestamp_millis() + Duration::from_weeks(6).as_millis() as i64
+    )
+    .execute(conn)
+    .await?;
+
+    Ok((token, refresh_token))
+}
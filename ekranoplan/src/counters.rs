@@ -0,0 +1,253 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+//! Counts shown on profiles and threads, kept in their own tables and
+//! bumped in the same transaction as whatever they count. Anything that
+//! slips past that is repaired by [`reconcile`].
+
+use std::{env, time::Duration};
+
+use models::Track;
+use sqlx::{PgConnection, PgPool};
+
+use crate::routes::tracks::{QUOTE_TRACK, REPOST_TRACK};
+
+/// Changes to an actor's counters, left at zero for the ones that don't change.
+#[derive(Debug, Default)]
+pub struct ActorDelta {
+    pub followed: i64,
+    pub followers: i64,
+    pub tracks: i64,
+}
+
+/// Changes to a track's counters, left at zero for the ones that don't change.
+#[derive(Debug, Default)]
+pub struct TrackDelta {
+    pub comments: i64,
+    pub bookmarks: i64,
+    pub reposts: i64,
+    pub quotes: i64,
+}
+
+pub async fn bump_actor(
+    conn: &mut PgConnection,
+    actor_id: &str,
+    delta: ActorDelta,
+) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "INSERT INTO actor_counters (actor_id, followed, followers, tracks) VALUES ($1, GREATEST($2::BIGINT, 0), GREATEST($3::BIGINT, 0), GREATEST($4::BIGINT, 0))
+        ON CONFLICT (actor_id) DO UPDATE SET followed = actor_counters.followed + $2, followers = actor_counters.followers + $3, tracks = actor_counters.tracks + $4;",
+        actor_id,
+        delta.followed,
+        delta.followers,
+        delta.tracks
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn bump_track(
+    conn: &mut PgConnection,
+    track_id: &str,
+    delta: TrackDelta,
+) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "INSERT INTO track_counters (track_id, comments, bookmarks, reposts, quotes) VALUES ($1, GREATEST($2::BIGINT, 0), GREATEST($3::BIGINT, 0), GREATEST($4::BIGINT, 0), GREATEST($5::BIGINT, 0))
+        ON CONFLICT (track_id) DO UPDATE SET comments = track_counters.comments + $2, bookmarks = track_counters.bookmarks + $3, reposts = track_counters.reposts + $4, quotes = track_counters.quotes + $5;",
+        track_id,
+        delta.comments,
+        delta.bookmarks,
+        delta.reposts,
+        delta.quotes
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+pub async fn bump_reactions(
+    conn: &mut PgConnection,
+    track_id: &str,
+    kind: i64,
+    delta: i64,
+) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "INSERT INTO track_reaction_counters (track_id, type, reactions) VALUES ($1, $2, GREATEST($3::BIGINT, 0))
+        ON CONFLICT (track_id, type) DO UPDATE SET reactions = track_reaction_counters.reactions + $3;",
+        track_id,
+        kind,
+        delta
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Bumps whatever `track` counts towards, by `sign` being 1 when it's
+/// created and -1 when it's removed.
+pub async fn count_track(
+    conn: &mut PgConnection,
+    track: &Track,
+    sign: i64,
+) -> Result<(), crate::Error> {
+    if let Some(ref parent_id) = track.parent_id {
+        bump_track(
+            conn,
+            parent_id,
+            TrackDelta {
+                comments: sign,
+                ..Default::default()
+            },
+        )
+        .await?;
+    } else if let Some(ref author_id) = track.author_id {
+        bump_actor(
+            conn,
+            author_id,
+            ActorDelta {
+                tracks: sign,
+                ..Default::default()
+            },
+        )
+        .await?;
+    }
+
+    if let Some(ref reference_id) = track.reference_id {
+        let delta = if track.r#type == REPOST_TRACK {
+            TrackDelta {
+                reposts: sign,
+                ..Default::default()
+            }
+        } else {
+            TrackDelta {
+                quotes: sign,
+                ..Default::default()
+            }
+        };
+        bump_track(conn, reference_id, delta).await?;
+    }
+
+    Ok(())
+}
+
+/// Recounts every counter from scratch, returning how many were off.
+///
+/// Each count is taken alongside the counter as it was in the same snapshot,
+/// and only their difference is applied, so bumps landing meanwhile are kept
+/// without locking anything but the counters being fixed.
+pub async fn reconcile(pg: &PgPool) -> Result<u64, crate::Error> {
+    let actors = sqlx::query!(
+        "WITH counted AS (
+            SELECT actors.id,
+                COALESCE(followed.count, 0) AS followed,
+                COALESCE(followers.count, 0) AS followers,
+                COALESCE(roots.count, 0) AS tracks,
+                COALESCE(seen.followed, 0) AS seen_followed,
+                COALESCE(seen.followers, 0) AS seen_followers,
+                COALESCE(seen.tracks, 0) AS seen_tracks
+            FROM actors
+            LEFT JOIN (SELECT follower_id, COUNT(*) FROM follows GROUP BY follower_id) AS followed ON followed.follower_id = actors.id
+            LEFT JOIN (SELECT followee_id, COUNT(*) FROM follows GROUP BY followee_id) AS followers ON followers.followee_id = actors.id
+            LEFT JOIN (SELECT author_id, COUNT(*) FROM tracks WHERE parent_id IS NULL GROUP BY author_id) AS roots ON roots.author_id = actors.id
+            LEFT JOIN actor_counters AS seen ON seen.actor_id = actors.id
+        )
+        INSERT INTO actor_counters (actor_id, followed, followers, tracks)
+        SELECT id, followed - seen_followed, followers - seen_followers, tracks - seen_tracks FROM counted
+        WHERE (followed, followers, tracks) IS DISTINCT FROM (seen_followed, seen_followers, seen_tracks)
+        ON CONFLICT (actor_id) DO UPDATE SET followed = actor_counters.followed + EXCLUDED.followed, followers = actor_counters.followers + EXCLUDED.followers, tracks = actor_counters.tracks + EXCLUDED.tracks;"
+    )
+    .execute(pg)
+    .await?
+    .rows_affected();
+    let tracks = sqlx::query!(
+        "WITH counted AS (
+            SELECT tracks.id,
+                COALESCE(comments.count, 0) AS comments,
+                COALESCE(bookmarks.count, 0) AS bookmarks,
+                COALESCE(refs.reposts, 0) AS reposts,
+                COALESCE(refs.quotes, 0) AS quotes,
+                COALESCE(seen.comments, 0) AS seen_comments,
+                COALESCE(seen.bookmarks, 0) AS seen_bookmarks,
+                COALESCE(seen.reposts, 0) AS seen_reposts,
+                COALESCE(seen.quotes, 0) AS seen_quotes
+            FROM tracks
+            LEFT JOIN (SELECT parent_id, COUNT(*) FROM tracks WHERE parent_id IS NOT NULL GROUP BY parent_id) AS comments ON comments.parent_id = tracks.id
+            LEFT JOIN (SELECT track_id, COUNT(*) FROM track_bookmarks GROUP BY track_id) AS bookmarks ON bookmarks.track_id = tracks.id
+            LEFT JOIN (
+                SELECT reference_id, COUNT(*) FILTER (WHERE type = $1) AS reposts, COUNT(*) FILTER (WHERE type = $2) AS quotes
+                FROM tracks WHERE reference_id IS NOT NULL GROUP BY reference_id
+            ) AS refs ON refs.reference_id = tracks.id
+            LEFT JOIN track_counters AS seen ON seen.track_id = tracks.id
+        )
+        INSERT INTO track_counters (track_id, comments, bookmarks, reposts, quotes)
+        SELECT id, comments - seen_comments, bookmarks - seen_bookmarks, reposts - seen_reposts, quotes - seen_quotes FROM counted
+        WHERE (comments, bookmarks, reposts, quotes) IS DISTINCT FROM (seen_comments, seen_bookmarks, seen_reposts, seen_quotes)
+        ON CONFLICT (track_id) DO UPDATE SET comments = track_counters.comments + EXCLUDED.comments, bookmarks = track_counters.bookmarks + EXCLUDED.bookmarks, reposts = track_counters.reposts + EXCLUDED.reposts, quotes = track_counters.quotes + EXCLUDED.quotes;",
+        REPOST_TRACK,
+        QUOTE_TRACK
+    )
+    .execute(pg)
+    .await?
+    .rows_affected();
+    // counters of reactions which are all gone are zeroed rather than
+    // deleted, as a reaction added meanwhile may have bumped them already
+    let reactions = sqlx::query!(
+        "WITH counted AS (
+            SELECT COALESCE(reactions.track_id, seen.track_id) AS track_id,
+                COALESCE(reactions.type, seen.type) AS type,
+                COALESCE(reactions.count, 0) AS reactions,
+                COALESCE(seen.reactions, 0) AS seen_reactions
+            FROM (SELECT track_id, type, COUNT(*) FROM track_reactions GROUP BY track_id, type) AS reactions
+            FULL JOIN track_reaction_counters AS seen ON seen.track_id = reactions.track_id AND seen.type = reactions.type
+        )
+        INSERT INTO track_reaction_counters (track_id, type, reactions)
+        SELECT track_id, type, reactions - seen_reactions FROM counted
+        WHERE reactions != seen_reactions
+        ON CONFLICT (track_id, type) DO UPDATE SET reactions = track_reaction_counters.reactions + EXCLUDED.reactions;"
+    )
+    .execute(pg)
+    .await?
+    .rows_affected();
+
+    Ok(actors + tracks + reactions)
+}
+
+/// Runs [`reconcile`], logging what it fixed or why it failed.
+pub async fn reconcile_logged(pg: &PgPool) {
+    match reconcile(pg).await {
+        Ok(0) => {}
+        Ok(fixed) => eprintln!("counter reconciliation fixed {fixed} counters"),
+        Err(err) => eprintln!("counter reconciliation failed: {err:?}"),
+    }
+}
+
+/// Runs [`reconcile`] every `COUNTER_RECONCILE_HOURS` hours, 24 by default.
+pub async fn reconcile_periodically(pg: PgPool) {
+    let hours = env::var("COUNTER_RECONCILE_HOURS")
+        .ok()
+        .and_then(|h| h.parse().ok())
+        .unwrap_or(24)
+        // a zero interval isn't one
+        .max(1);
+    let mut interval = tokio::time::interval(Duration::from_hours(hours));
+    // the first tick is immediate, skip it rather than recount on every start
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        reconcile_logged(&pg).await;
+    }
+}
@@ -0,0 +1,147 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use std::{env, time::Duration};
+
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, types::chrono};
+
+use crate::{Error, mailer::Mail};
+
+pub const VERIFY_EMAIL: i32 = 0;
+pub const RESET_PASSWORD: i32 = 1;
+
+/// A signed, single-use token mailed out to prove ownership of an address.
+/// Only the token id is stored, and it's deleted once the token is used.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailClaims {
+    pub exp: usize,
+    pub iat: usize,
+    pub sub: String,
+    pub jti: String,
+    pub purpose: i32,
+    pub email: String,
+}
+
+fn lifetime(purpose: i32) -> Duration {
+    if purpose == RESET_PASSWORD {
+        Duration::from_secs(60 * 60)
+    } else {
+        Duration::from_secs(24 * 60 * 60)
+    }
+}
+
+/// Issues a new token for `purpose`, invalidating any older ones.
+pub async fn issue(
+    conn: &mut PgConnection,
+    user_id: &str,
+    email: &str,
+    purpose: i32,
+    key: &str,
+) -> Result<String, Error> {
+    let now = chrono::Utc::now();
+    let claims = EmailClaims {
+        exp: now.timestamp() as usize + lifetime(purpose).as_secs() as usize,
+        iat: now.timestamp() as usize,
+        sub: user_id.to_string(),
+        jti: nanoid::nanoid!(),
+        purpose,
+        email: email.to_string(),
+    };
+
+    sqlx::query!(
+        "DELETE FROM email_tokens WHERE user_id = $1 AND purpose = $2;",
+        user_id,
+        purpose
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "INSERT INTO email_tokens (id, user_id, purpose, expires_at) VALUES ($1, $2, $3, $4);",
+        claims.jti,
+        user_id,
+        purpose,
+        now.timestamp_millis() + lifetime(purpose).as_millis() as i64
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(encode(
+        &Header::new(jsonwebtoken::Algorithm::HS256),
+        &claims,
+        &EncodingKey::from_secret(key.as_bytes()),
+    )?)
+}
+
+/// Checks `token` was issued for `purpose` and uses it up.
+pub async fn consume(
+    conn: &mut PgConnection,
+    token: &str,
+    purpose: i32,
+    key: &str,
+) -> Result<EmailClaims, Error> {
+    let claims = decode::<EmailClaims>(
+        token,
+        &DecodingKey::from_secret(key.as_bytes()),
+        &Validation::new(jsonwebtoken::Algorithm::HS256),
+    )?
+    .claims;
+
+    if claims.purpose != purpose {
+        return Err(Error::BadToken);
+    }
+
+    sqlx::query!(
+        "DELETE FROM email_tokens WHERE id = $1 AND user_id = $2 AND purpose = $3 RETURNING id;",
+        claims.jti,
+        claims.sub,
+        purpose
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(Error::BadToken)?;
+
+    Ok(claims)
+}
+
+fn app_url() -> String {
+    env::var("APP_URL").unwrap_or_else(|_| "http://localhost:5173".to_string())
+}
+
+pub fn verification_mail(to: &str, token: &str) -> Mail {
+    Mail {
+        to: to.to_string(),
+        subject: "Verify your email".to_string(),
+        body: format!(
+            "Confirm this is your email address by opening the link below.\n\n{}/verify-email?token={}\n\nIt expires in 24 hours.",
+            app_url(),
+            token
+        ),
+    }
+}
+
+pub fn reset_mail(to: &str, token: &str) -> Mail {
+    Mail {
+        to: to.to_string(),
+        subject: "Reset your password".to_string(),
+        body: format!(
+            "Someone asked to reset the password of your account. If this was you, open the link below to choose a new one.\n\n{}/reset-password?token={}\n\nIt expires in an hour. If this wasn't you, you can ignore this email.",
+            app_url(),
+            token
+        ),
+    }
+}
@@ -15,116 +15,375 @@
 */
 
 use std::{
+    collections::BTreeMap,
     io::{self, IntoInnerError},
     string::FromUtf8Error,
 };
 
-use axum::{extract::multipart::MultipartError, http::header::ToStrError};
+use axum::{
+    Json,
+    extract::{
+        multipart::MultipartError,
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+    },
+    http::{StatusCode, header::ToStrError},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
 
-#[derive(Debug, thiserror::Error, axum_thiserror::ErrorStatus)]
+#[derive(Debug, thiserror::Error)]
 #[allow(clippy::enum_variant_names)]
 pub enum Error {
     #[error("Internal Server Error")]
-    #[status(500)]
     DBError(#[from] sqlx::Error),
 
     #[error("Internal Server Error")]
-    #[status(500)]
     VodoError(#[from] vodozemac::KeyError),
 
     #[error("Internal Server Error")]
-    #[status(500)]
     VodoError2(#[from] vodozemac::PickleError),
 
     #[error("Internal Server Error")]
-    #[status(500)]
     ToStrError(#[from] ToStrError),
 
     #[error("Internal Server Error")]
-    #[status(500)]
     MultipartError(#[from] MultipartError),
 
     #[error("Internal Server Error")]
-    #[status(500)]
     ImageError(#[from] image::ImageError),
 
     #[error("Internal Server Error")]
-    #[status(500)]
     S3Error(#[from] s3::error::S3Error),
 
     #[error("Internal Server Error")]
-    #[status(500)]
     FailedPasswordHash,
 
     #[error("Internal Server Error")]
-    #[status(500)]
     IdenticonError(#[from] identicon_rs::error::IdenticonError),
 
     #[error("Internal Server Error")]
-    #[status(500)]
     UTF8Error(#[from] FromUtf8Error),
 
     #[error("Internal Server Error")]
-    #[status(500)]
     BufWriterError(#[from] IntoInnerError<Vec<u8>>),
 
     #[error("Internal Server Error")]
-    #[status(500)]
     CBORError(#[from] ciborium::ser::Error<io::Error>),
 
     #[error("Internal Server Error")]
-    #[status(500)]
     SendError,
 
+    #[error("Internal Server Error")]
+    UnknownPickleKey,
+
+    #[error("{}", .0.body_text())]
+    MalformedBody(#[from] JsonRejection),
+
+    #[error("{}", .0.body_text())]
+    InvalidPath(#[from] PathRejection),
+
+    #[error("{}", .0.body_text())]
+    InvalidQuery(#[from] QueryRejection),
+
+    #[error("Internal Server Error")]
+    MailError,
+
     #[error("Invalid Token")]
-    #[status(401)]
     InvalidToken(#[from] jsonwebtoken::errors::Error),
 
     #[error("Invalid Token")]
-    #[status(401)]
     BadToken,
 
     #[error("Expired Session")]
-    #[status(401)]
     ExpiredSession,
 
+    #[error("Session does not exist")]
+    SessionNotExist,
+
     #[error("Invalid email or password")]
-    #[status(401)]
     Argon2Error,
 
+    #[error("Administrator privileges required")]
+    NotAdmin,
+
+    #[error("Invalid two-factor code")]
+    InvalidTotpCode,
+
+    #[error("Two-factor authentication already enabled")]
+    TotpEnabled,
+
+    #[error("Two-factor authentication not set up")]
+    TotpNotEnabled,
+
+    #[error("Too many wrong codes, try again later")]
+    TooManyAttempts,
+
+    #[error("Invalid email address")]
+    InvalidEmail,
+
+    #[error("Email address already in use")]
+    EmailTaken,
+
+    #[error("Account has no email address")]
+    NoEmail,
+
     #[error("Valid former password required")]
-    #[status(400)]
     InvalidFormerPassword,
 
     #[error("User already followed")]
-    #[status(400)]
     UserFollowed,
 
     #[error("User not followed")]
-    #[status(400)]
     UserNotFollowed,
 
     #[error("User not found")]
-    #[status(404)]
     UserNotFound,
 
+    #[error("Follow already requested")]
+    FollowRequested,
+
+    #[error("Follow request not found")]
+    FollowRequestNotExist,
+
+    #[error("This account is private")]
+    AccountPrivate,
+
+    #[error("User already blocked")]
+    UserBlocked,
+
+    #[error("User not blocked")]
+    UserNotBlocked,
+
+    #[error("User already muted")]
+    UserMuted,
+
+    #[error("User not muted")]
+    UserNotMuted,
+
+    #[error("You can't interact with this user")]
+    Blocked,
+
+    #[error("Handles must be 3 to 20 letters, numbers or underscores")]
+    InvalidHandle,
+
+    #[error("Handle is reserved")]
+    HandleReserved,
+
+    #[error("Handle already taken")]
+    HandleTaken,
+
+    #[error("Handle was changed too recently")]
+    HandleCooldown,
+
     #[error("Track does not exist")]
-    #[status(404)]
     TrackNotExist,
 
+    #[error("Reposts cannot be edited")]
+    RepostNotEditable,
+
+    #[error("You can't reply to this track")]
+    ReplyRestricted,
+
     #[error("Reaction does not exist")]
-    #[status(400)]
     ReactionNotExist,
 
     #[error("Reaction already exists")]
-    #[status(400)]
     ReactionExists,
 
+    #[error("Unknown reaction")]
+    InvalidReaction,
+
+    #[error("Track already reposted")]
+    RepostExists,
+
+    #[error("Repost does not exist")]
+    RepostNotExist,
+
+    #[error("Room does not exist")]
+    RoomNotExist,
+
+    #[error("Message does not exist")]
+    MessageNotExist,
+
+    #[error("Room is not a group")]
+    RoomNotGroup,
+
+    #[error("Only the room owner can do this")]
+    NotRoomOwner,
+
+    #[error("Room is full")]
+    RoomFull,
+
+    #[error("User already in room")]
+    MemberExists,
+
+    #[error("User not in room")]
+    MemberNotExist,
+
+    #[error("Invalid device keys")]
+    InvalidKeys,
+
+    #[error("Invalid signature")]
+    InvalidSignature,
+
+    #[error("Invalid ciphertext")]
+    InvalidCiphertext,
+
+    #[error("Device keys cannot be changed")]
+    DeviceKeysChanged,
+
+    #[error("Device does not exist")]
+    DeviceNotExist,
+
+    #[error("Too many devices")]
+    TooManyDevices,
+
+    #[error("Too many one-time keys")]
+    TooManyKeys,
+
     #[error("Image type not supported")]
-    #[status(400)]
     InvalidImageType,
 
     #[error("Image not found")]
-    #[status(400)]
     ImageNotFound,
 }
+
+/// What every error response looks like. `code` is stable and meant for
+/// clients to branch on, `message` may change and is meant for people.
+#[derive(Debug, Serialize)]
+pub struct ErrorBody {
+    pub code: &'static str,
+    pub status: u16,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<BTreeMap<String, Vec<String>>>,
+    /// Set on internal errors, matches the id logged alongside the cause.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+}
+
+impl IntoResponse for ErrorBody {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (status, Json(self)).into_response()
+    }
+}
+
+impl Error {
+    pub fn status(&self) -> StatusCode {
+        self.kind().0
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind().1
+    }
+
+    fn kind(&self) -> (StatusCode, &'static str) {
+        match self {
+            Self::DBError(_)
+            | Self::VodoError(_)
+            | Self::VodoError2(_)
+            | Self::ToStrError(_)
+            | Self::MultipartError(_)
+            | Self::ImageError(_)
+            | Self::S3Error(_)
+            | Self::FailedPasswordHash
+            | Self::IdenticonError(_)
+            | Self::UTF8Error(_)
+            | Self::BufWriterError(_)
+            | Self::CBORError(_)
+            | Self::SendError
+            | Self::UnknownPickleKey
+            | Self::MailError => (StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+            Self::MalformedBody(rejection) => (rejection.status(), "malformed_body"),
+            Self::InvalidPath(rejection) => (rejection.status(), "invalid_path"),
+            Self::InvalidQuery(rejection) => (rejection.status(), "invalid_query"),
+            Self::InvalidToken(_) => (StatusCode::UNAUTHORIZED, "invalid_token"),
+            Self::BadToken => (StatusCode::UNAUTHORIZED, "invalid_token"),
+            Self::ExpiredSession => (StatusCode::UNAUTHORIZED, "expired_session"),
+            Self::SessionNotExist => (StatusCode::NOT_FOUND, "session_not_exist"),
+            Self::Argon2Error => (StatusCode::UNAUTHORIZED, "invalid_credentials"),
+            Self::NotAdmin => (StatusCode::FORBIDDEN, "not_admin"),
+            Self::InvalidTotpCode => (StatusCode::UNAUTHORIZED, "invalid_totp_code"),
+            Self::TotpEnabled => (StatusCode::BAD_REQUEST, "totp_enabled"),
+            Self::TotpNotEnabled => (StatusCode::BAD_REQUEST, "totp_not_enabled"),
+            Self::TooManyAttempts => (StatusCode::TOO_MANY_REQUESTS, "too_many_attempts"),
+            Self::InvalidEmail => (StatusCode::BAD_REQUEST, "invalid_email"),
+            Self::EmailTaken => (StatusCode::BAD_REQUEST, "email_taken"),
+            Self::NoEmail => (StatusCode::BAD_REQUEST, "no_email"),
+            Self::InvalidFormerPassword => (StatusCode::BAD_REQUEST, "invalid_former_password"),
+            Self::UserFollowed => (StatusCode::BAD_REQUEST, "user_followed"),
+            Self::UserNotFollowed => (StatusCode::BAD_REQUEST, "user_not_followed"),
+            Self::UserBlocked => (StatusCode::BAD_REQUEST, "user_blocked"),
+            Self::UserNotBlocked => (StatusCode::BAD_REQUEST, "user_not_blocked"),
+            Self::UserMuted => (StatusCode::BAD_REQUEST, "user_muted"),
+            Self::UserNotMuted => (StatusCode::BAD_REQUEST, "user_not_muted"),
+            Self::Blocked => (StatusCode::FORBIDDEN, "blocked"),
+            Self::UserNotFound => (StatusCode::NOT_FOUND, "user_not_found"),
+            Self::FollowRequested => (StatusCode::BAD_REQUEST, "follow_requested"),
+            Self::FollowRequestNotExist => (StatusCode::NOT_FOUND, "follow_request_not_exist"),
+            Self::AccountPrivate => (StatusCode::FORBIDDEN, "account_private"),
+            Self::InvalidHandle => (StatusCode::BAD_REQUEST, "invalid_handle"),
+            Self::HandleReserved => (StatusCode::BAD_REQUEST, "handle_reserved"),
+            Self::HandleTaken => (StatusCode::BAD_REQUEST, "handle_taken"),
+            Self::HandleCooldown => (StatusCode::TOO_MANY_REQUESTS, "handle_cooldown"),
+            Self::TrackNotExist => (StatusCode::NOT_FOUND, "track_not_exist"),
+            Self::RepostNotEditable => (StatusCode::BAD_REQUEST, "repost_not_editable"),
+            Self::ReplyRestricted => (StatusCode::FORBIDDEN, "reply_restricted"),
+            Self::ReactionNotExist => (StatusCode::BAD_REQUEST, "reaction_not_exist"),
+            Self::ReactionExists => (StatusCode::BAD_REQUEST, "reaction_exists"),
+            Self::InvalidReaction => (StatusCode::BAD_REQUEST, "invalid_reaction"),
+            Self::RepostExists => (StatusCode::BAD_REQUEST, "repost_exists"),
+            Self::RepostNotExist => (StatusCode::NOT_FOUND, "repost_not_exist"),
+            Self::RoomNotExist => (StatusCode::NOT_FOUND, "room_not_exist"),
+            Self::MessageNotExist => (StatusCode::NOT_FOUND, "message_not_exist"),
+            Self::RoomNotGroup => (StatusCode::BAD_REQUEST, "room_not_group"),
+            Self::NotRoomOwner => (StatusCode::FORBIDDEN, "not_room_owner"),
+            Self::RoomFull => (StatusCode::BAD_REQUEST, "room_full"),
+            Self::MemberExists => (StatusCode::BAD_REQUEST, "member_exists"),
+            Self::MemberNotExist => (StatusCode::NOT_FOUND, "member_not_exist"),
+            Self::InvalidKeys => (StatusCode::BAD_REQUEST, "invalid_keys"),
+            Self::InvalidSignature => (StatusCode::BAD_REQUEST, "invalid_signature"),
+            Self::InvalidCiphertext => (StatusCode::BAD_REQUEST, "invalid_ciphertext"),
+            Self::DeviceKeysChanged => (StatusCode::BAD_REQUEST, "device_keys_changed"),
+            Self::DeviceNotExist => (StatusCode::NOT_FOUND, "device_not_exist"),
+            Self::TooManyDevices => (StatusCode::BAD_REQUEST, "too_many_devices"),
+            Self::TooManyKeys => (StatusCode::BAD_REQUEST, "too_many_keys"),
+            Self::InvalidImageType => (StatusCode::BAD_REQUEST, "invalid_image_type"),
+            Self::ImageNotFound => (StatusCode::BAD_REQUEST, "image_not_found"),
+        }
+    }
+
+    /// The body field at fault, for errors only found out past validation.
+    fn field(&self) -> Option<&'static str> {
+        match self {
+            Self::EmailTaken => Some("email"),
+            Self::HandleReserved | Self::HandleTaken => Some("handle"),
+            _ => None,
+        }
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let correlation_id = if status.is_server_error() {
+            let id = nanoid::nanoid!();
+            eprintln!("[{id}] internal error: {self:?}");
+            Some(id)
+        } else {
+            None
+        };
+
+        ErrorBody {
+            code: self.code(),
+            status: status.as_u16(),
+            message: self.to_string(),
+            fields: self
+                .field()
+                .map(|field| BTreeMap::from([(field.to_string(), vec![self.to_string()])])),
+            correlation_id,
+        }
+        .into_response()
+    }
+}
@@ -0,0 +1,113 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use std::{env, fmt::Debug, io::Write, path::PathBuf, sync::Arc};
+
+use futures::future::BoxFuture;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor, message::Mailbox};
+
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+pub trait Mailer: Debug + Send + Sync {
+    fn send(&self, mail: Mail) -> BoxFuture<'_, Result<(), crate::Error>>;
+}
+
+/// Sends mail through the SMTP server at `SMTP_URL`, from `MAIL_FROM`.
+#[derive(Debug)]
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, mail: Mail) -> BoxFuture<'_, Result<(), crate::Error>> {
+        Box::pin(async move {
+            let message = Message::builder()
+                .from(self.from.clone())
+                .to(mail.to.parse().map_err(|_| crate::Error::InvalidEmail)?)
+                .subject(mail.subject)
+                .body(mail.body)
+                .map_err(|_| crate::Error::MailError)?;
+            self.transport
+                .send(message)
+                .await
+                .map_err(|_| crate::Error::MailError)?;
+            Ok(())
+        })
+    }
+}
+
+/// Appends mail to `MAIL_LOG_FILE`, or prints it if that isn't set, instead
+/// of sending it. Meant for local development and tests.
+#[derive(Debug)]
+pub struct LogMailer {
+    path: Option<PathBuf>,
+}
+
+impl Mailer for LogMailer {
+    fn send(&self, mail: Mail) -> BoxFuture<'_, Result<(), crate::Error>> {
+        Box::pin(async move {
+            let entry = format!(
+                "To: {}\nSubject: {}\n\n{}\n\n",
+                mail.to, mail.subject, mail.body
+            );
+            if let Some(ref path) = self.path {
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .and_then(|mut f| f.write_all(entry.as_bytes()))
+                    .map_err(|_| crate::Error::MailError)?;
+            } else {
+                eprint!("{entry}");
+            }
+            Ok(())
+        })
+    }
+}
+
+pub fn from_env() -> Arc<dyn Mailer> {
+    if let Ok(url) = env::var("SMTP_URL") {
+        Arc::new(SmtpMailer {
+            transport: AsyncSmtpTransport::<Tokio1Executor>::from_url(&url)
+                .expect("Invalid SMTP url")
+                .build(),
+            from: env::var("MAIL_FROM")
+                .expect("Could not find mail sender address in environment variables")
+                .parse()
+                .expect("Invalid mail sender address"),
+        })
+    } else {
+        Arc::new(LogMailer {
+            path: env::var("MAIL_LOG_FILE").ok().map(PathBuf::from),
+        })
+    }
+}
+
+/// Sends `mail` in the background so requests don't wait on the mail server.
+pub fn send_later(mailer: &Arc<dyn Mailer>, mail: Mail) {
+    let mailer = mailer.clone();
+    tokio::spawn(async move {
+        if let Err(err) = mailer.send(mail).await {
+            eprintln!("failed to send mail: {err:?}");
+        }
+    });
+}
@@ -17,16 +17,25 @@
 #![feature(duration_constructors)]
 
 mod auth;
+mod counters;
+mod email;
 mod error;
+mod extract;
+mod mailer;
+mod mfa;
+mod pickle;
 mod routes;
 mod snow;
 mod utils;
+mod validation;
 
 use error::Error;
+use mailer::Mailer;
+use pickle::PickleKeys;
 use s3::{Bucket, creds::Credentials};
 use serde::Serialize;
 use snow::SnowflakeGenerator;
-use std::{collections::HashMap, env, sync::Arc, time::Duration};
+use std::{collections::HashMap, env, net::SocketAddr, sync::Arc, time::Duration};
 
 use axum::{http::Method, response::sse::Event};
 use mimalloc::MiMalloc;
@@ -53,9 +62,46 @@ pub enum X15Message {
         room_id: String,
         msg: models::Message,
     },
+    MessageUpdate {
+        room_id: String,
+        msg: models::Message,
+    },
+    MessageDelete {
+        room_id: String,
+        message_id: String,
+    },
+    ReadStateUpdate {
+        state: models::ReadState,
+    },
+    RoomUpdate {
+        room: models::Room,
+    },
+    MemberAdd {
+        room_id: String,
+        actor: models::Actor,
+    },
+    MemberRemove {
+        room_id: String,
+        actor_id: String,
+    },
+    DeviceMessageCreate {
+        msg: models::DeviceMessage,
+    },
+    FollowRequestCreate {
+        request: models::Follow,
+    },
+    FollowRequestAccept {
+        profile: models::UserProfile,
+    },
 }
 
-type ConsumantsMap = HashMap<String, Vec<mpsc::Sender<Result<Event, crate::Error>>>>;
+#[derive(Debug)]
+pub struct Consumant {
+    pub session_id: String,
+    pub sender: mpsc::Sender<Result<Event, crate::Error>>,
+}
+
+type ConsumantsMap = HashMap<String, Vec<Consumant>>;
 
 #[derive(Debug, Clone)]
 pub struct GSt {
@@ -64,11 +110,11 @@ pub struct GSt {
     pub avatars: Bucket,
     pub banners: Bucket,
     pub snow: Arc<SnowflakeGenerator>,
+    pub pickles: Arc<PickleKeys>,
+    pub mailer: Arc<dyn Mailer>,
     pub consumants: Arc<RwLock<ConsumantsMap>>,
 }
 
-pub const PICKLE_KEY: [u8; 32] = [0u8; 32];
-
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
@@ -118,6 +164,8 @@ async fn main() {
     let banners = Bucket::new(BANNERS_BUCKET_NAME, region.clone(), credentials.clone())
         .expect("Failed to get S3 banners bucket");
 
+    tokio::spawn(counters::reconcile_periodically(pool.clone()));
+
     let cors = CorsLayer::new()
         .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::PATCH])
         .allow_headers(Any)
@@ -133,9 +181,16 @@ async fn main() {
             avatars,
             banners,
             snow: Arc::new(snow::SnowflakeGenerator::default()),
+            pickles: Arc::new(PickleKeys::from_env()),
+            mailer: mailer::from_env(),
             consumants: Arc::new(RwLock::new(HashMap::new())),
         });
 
     let listener = TcpListener::bind("0.0.0.0:24650").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
@@ -0,0 +1,303 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use std::time::Duration;
+
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use models::Account;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool, types::chrono};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::Error;
+
+static ISSUER: &str = "Derailed";
+static RECOVERY_CODES: usize = 10;
+static CHALLENGE_LIFETIME: Duration = Duration::from_secs(5 * 60);
+/// Wrong codes a challenge takes before it's used up.
+static CHALLENGE_FAILURES: i32 = 5;
+/// Wrong codes an account takes, across challenges, before it's locked out
+/// of two-factor authentication for `LOCKOUT`.
+static ACCOUNT_FAILURES: i32 = 10;
+static LOCKOUT: Duration = Duration::from_secs(15 * 60);
+static STEP: u64 = 30;
+
+/// Handed out by `/login` in place of a token when an account has two-factor
+/// authentication on, and exchanged at `/login/totp` along with a code.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Challenge {
+    pub exp: usize,
+    pub iat: usize,
+    pub sub: String,
+    pub mfa: bool,
+    pub jti: String,
+}
+
+impl Challenge {
+    pub fn new(user_id: String) -> Self {
+        let now = chrono::Utc::now().timestamp() as usize;
+        Self {
+            exp: now + CHALLENGE_LIFETIME.as_secs() as usize,
+            iat: now,
+            sub: user_id,
+            mfa: true,
+            jti: nanoid::nanoid!(),
+        }
+    }
+
+    /// Stores the challenge so it can be answered, clearing out expired ones.
+    pub async fn save(&self, db: &PgPool) -> Result<(), Error> {
+        sqlx::query!(
+            "DELETE FROM mfa_challenges WHERE expires_at < $1;",
+            chrono::Utc::now().timestamp()
+        )
+        .execute(db)
+        .await?;
+        sqlx::query!(
+            "INSERT INTO mfa_challenges (id, user_id, expires_at) VALUES ($1, $2, $3);",
+            self.jti,
+            self.sub,
+            self.exp as i64
+        )
+        .execute(db)
+        .await?;
+        Ok(())
+    }
+
+    /// Fails with [`Error::BadToken`] once the challenge was answered or used
+    /// up by wrong codes.
+    pub async fn check(&self, db: &PgPool) -> Result<(), Error> {
+        sqlx::query!(
+            "SELECT id FROM mfa_challenges WHERE id = $1 AND user_id = $2;",
+            self.jti,
+            self.sub
+        )
+        .fetch_optional(db)
+        .await?
+        .ok_or(Error::BadToken)?;
+        Ok(())
+    }
+
+    /// Uses up the challenge once it has been answered.
+    pub async fn consume(&self, conn: &mut PgConnection) -> Result<(), Error> {
+        let removed = sqlx::query!(
+            "DELETE FROM mfa_challenges WHERE id = $1 AND user_id = $2;",
+            self.jti,
+            self.sub
+        )
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+        if removed == 0 {
+            Err(Error::BadToken)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn make_token(&self, key: &EncodingKey) -> Result<String, Error> {
+        Ok(encode(
+            &Header::new(jsonwebtoken::Algorithm::HS256),
+            self,
+            key,
+        )?)
+    }
+
+    pub fn from_token(token: &str, key: &DecodingKey) -> Result<Self, Error> {
+        let challenge =
+            decode::<Self>(token, key, &Validation::new(jsonwebtoken::Algorithm::HS256))?.claims;
+        if challenge.mfa {
+            Ok(challenge)
+        } else {
+            Err(Error::BadToken)
+        }
+    }
+}
+
+pub fn new_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+pub fn totp(secret: &str, account_name: &str) -> Result<TOTP, Error> {
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        Secret::Encoded(secret.to_string())
+            .to_bytes()
+            .map_err(|_| Error::InvalidTotpCode)?,
+        Some(ISSUER.to_string()),
+        account_name.to_string(),
+    )
+    .map_err(|_| Error::InvalidTotpCode)
+}
+
+/// Generates a fresh set of recovery codes for `user_id`, replacing any old
+/// ones. Only their hashes are kept so they can't be shown again.
+pub async fn new_recovery_codes(
+    conn: &mut PgConnection,
+    user_id: &str,
+) -> Result<Vec<String>, Error> {
+    let codes: Vec<String> = (0..RECOVERY_CODES)
+        .map(|_| {
+            nanoid::nanoid!(
+                10,
+                &"abcdefghijkmnpqrstuvwxyz23456789"
+                    .chars()
+                    .collect::<Vec<_>>()
+            )
+        })
+        .collect();
+    let hashes: Vec<String> = codes
+        .iter()
+        .map(|c| blake3::hash(c.as_bytes()).to_string())
+        .collect();
+
+    sqlx::query!("DELETE FROM recovery_codes WHERE user_id = $1;", user_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!(
+        "INSERT INTO recovery_codes (user_id, code_hash) SELECT $1, UNNEST($2::TEXT[]);",
+        user_id,
+        &hashes
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(codes)
+}
+
+/// Checks `code` against the authenticator for `secret`, allowing for one
+/// step of clock drift either way. Every step is only accepted once, so a
+/// code that was seen can't be replayed.
+pub async fn check_totp(
+    conn: &mut PgConnection,
+    user_id: &str,
+    secret: &str,
+    code: &str,
+) -> Result<bool, Error> {
+    let totp = totp(secret, user_id)?;
+    let step = chrono::Utc::now().timestamp() as u64 / STEP;
+
+    // compared through blake3 hashes, whose equality is constant time
+    let code = blake3::hash(code.trim().as_bytes());
+    let matched = (step.saturating_sub(1)..=step + 1)
+        .find(|s| blake3::hash(totp.generate(s * STEP).as_bytes()) == code);
+
+    if let Some(matched) = matched {
+        let accepted = sqlx::query!(
+            "UPDATE accounts SET totp_last_step = $1 WHERE id = $2 AND (totp_last_step IS NULL OR totp_last_step < $1);",
+            matched as i64,
+            user_id
+        )
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+        Ok(accepted > 0)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Fails with [`Error::TooManyAttempts`] while the account is locked out
+/// after too many wrong codes.
+pub fn check_lockout(account: &Account) -> Result<(), Error> {
+    let since = chrono::Utc::now().timestamp_millis() - LOCKOUT.as_millis() as i64;
+    if account.totp_failures >= ACCOUNT_FAILURES
+        && account.totp_failed_at.is_some_and(|at| at > since)
+    {
+        Err(Error::TooManyAttempts)
+    } else {
+        Ok(())
+    }
+}
+
+/// Counts a wrong code against the account and, when answering a login
+/// challenge, against that challenge, using it up after too many. This goes
+/// straight to `db` so the count survives the request failing.
+pub async fn record_failure(
+    db: &PgPool,
+    user_id: &str,
+    challenge: Option<&Challenge>,
+) -> Result<(), Error> {
+    let now = chrono::Utc::now().timestamp_millis();
+    sqlx::query!(
+        "UPDATE accounts SET totp_failures = CASE WHEN totp_failed_at > $2 THEN totp_failures + 1 ELSE 1 END, totp_failed_at = $3 WHERE id = $1;",
+        user_id,
+        now - LOCKOUT.as_millis() as i64,
+        now
+    )
+    .execute(db)
+    .await?;
+
+    if let Some(challenge) = challenge {
+        sqlx::query!(
+            "DELETE FROM mfa_challenges WHERE id = $1 AND failures + 1 >= $2;",
+            challenge.jti,
+            CHALLENGE_FAILURES
+        )
+        .execute(db)
+        .await?;
+        sqlx::query!(
+            "UPDATE mfa_challenges SET failures = failures + 1 WHERE id = $1;",
+            challenge.jti
+        )
+        .execute(db)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Checks `code` against the account's authenticator, falling back to its
+/// recovery codes, which are used up when they match. Accepted codes clear
+/// the account's count of wrong ones.
+pub async fn check_code(
+    conn: &mut PgConnection,
+    account: &Account,
+    code: &str,
+) -> Result<(), Error> {
+    let secret = account.totp_secret.as_ref().ok_or(Error::TotpNotEnabled)?;
+
+    if check_totp(conn, &account.id, secret, code).await? {
+        return clear_failures(conn, &account.id).await;
+    }
+
+    let used = sqlx::query!(
+        "DELETE FROM recovery_codes WHERE user_id = $1 AND code_hash = $2 RETURNING user_id;",
+        account.id,
+        blake3::hash(code.trim().to_lowercase().as_bytes()).to_string()
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if used.is_some() {
+        clear_failures(conn, &account.id).await
+    } else {
+        Err(Error::InvalidTotpCode)
+    }
+}
+
+async fn clear_failures(conn: &mut PgConnection, user_id: &str) -> Result<(), Error> {
+    sqlx::query!(
+        "UPDATE accounts SET totp_failures = 0, totp_failed_at = NULL WHERE id = $1;",
+        user_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,160 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use std::{collections::HashMap, env, fs, sync::Arc};
+
+use sqlx::PgPool;
+use vodozemac::olm::{Account, AccountPickle};
+
+/// Pickles written before key ids were stored alongside them have no prefix
+/// and were all encrypted with an all-zero key.
+static LEGACY_KEY_ID: &str = "legacy";
+static LEGACY_KEY: [u8; 32] = [0u8; 32];
+
+static ROTATION_BATCH: i64 = 100;
+
+/// The keys account pickles are encrypted with.
+///
+/// Keys are `id=base64key` pairs, either one per line in the file at
+/// `PICKLE_KEYS_FILE` or `;` separated in `PICKLE_KEYS`. New pickles are
+/// encrypted with the key named by `PICKLE_KEY_ID` (or the last one listed),
+/// the rest are only used to read pickles which haven't been rotated yet.
+#[derive(Debug)]
+pub struct PickleKeys {
+    pub current: String,
+    keys: HashMap<String, [u8; 32]>,
+}
+
+fn split_pickle(pickle: &str) -> (&str, &str) {
+    pickle.split_once(':').unwrap_or((LEGACY_KEY_ID, pickle))
+}
+
+impl PickleKeys {
+    pub fn from_env() -> Self {
+        let raw = if let Ok(path) = env::var("PICKLE_KEYS_FILE") {
+            fs::read_to_string(path).expect("Could not read pickle keys file")
+        } else {
+            env::var("PICKLE_KEYS").expect("Could not find pickle keys in environment variables")
+        };
+
+        let mut keys = HashMap::from([(LEGACY_KEY_ID.to_string(), LEGACY_KEY)]);
+        let mut last = None;
+        for pair in raw
+            .split([';', '\n'])
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+        {
+            let (id, key) = pair
+                .split_once('=')
+                .expect("Pickle keys must be formatted as id=base64key");
+            if id.contains(':') || id == LEGACY_KEY_ID {
+                panic!("Invalid pickle key id {id}");
+            }
+            let key: [u8; 32] = vodozemac::base64_decode(key)
+                .ok()
+                .and_then(|k| k.try_into().ok())
+                .expect("Pickle keys must be 32 base64 encoded bytes");
+            keys.insert(id.to_string(), key);
+            last = Some(id.to_string());
+        }
+
+        let current = env::var("PICKLE_KEY_ID")
+            .ok()
+            .or(last)
+            .expect("At least one pickle key must be configured");
+        if !keys.contains_key(&current) || current == LEGACY_KEY_ID {
+            panic!("Current pickle key {current} is not configured");
+        }
+
+        Self { current, keys }
+    }
+
+    pub fn encrypt(&self, account: &Account) -> String {
+        format!(
+            "{}:{}",
+            self.current,
+            account.pickle().encrypt(&self.keys[&self.current])
+        )
+    }
+
+    pub fn decrypt(&self, pickle: &str) -> Result<Account, crate::Error> {
+        let (key_id, ciphertext) = split_pickle(pickle);
+        let key = self
+            .keys
+            .get(key_id)
+            .ok_or(crate::Error::UnknownPickleKey)?;
+        Ok(Account::from_pickle(AccountPickle::from_encrypted(
+            ciphertext, key,
+        )?))
+    }
+
+    pub fn is_current(&self, pickle: &str) -> bool {
+        split_pickle(pickle).0 == self.current
+    }
+}
+
+/// Number of pickles not yet encrypted with the current key.
+pub async fn outdated(pg: &PgPool, keys: &PickleKeys) -> Result<i64, crate::Error> {
+    Ok(sqlx::query!(
+        "SELECT COUNT(id) FROM accounts WHERE pickle NOT LIKE $1;",
+        format!("{}:%", keys.current)
+    )
+    .fetch_one(pg)
+    .await?
+    .count
+    .unwrap_or(0))
+}
+
+/// Re-encrypts every pickle with the current key, returning how many were
+/// rotated. Accounts are updated one by one, and only if their pickle hasn't
+/// changed since it was read, so this is safe to run on a live instance.
+pub async fn rotate(pg: PgPool, keys: Arc<PickleKeys>) -> Result<u64, crate::Error> {
+    let mut rotated = 0;
+    let mut last_id = String::new();
+
+    loop {
+        let batch = sqlx::query!(
+            "SELECT id, pickle FROM accounts WHERE id > $1 AND pickle NOT LIKE $2 ORDER BY id LIMIT $3;",
+            last_id,
+            format!("{}:%", keys.current),
+            ROTATION_BATCH
+        )
+        .fetch_all(&pg)
+        .await?;
+
+        if batch.is_empty() {
+            return Ok(rotated);
+        }
+
+        for account in batch {
+            last_id = account.id.clone();
+            if keys.is_current(&account.pickle) {
+                continue;
+            }
+
+            let pickle = keys.encrypt(&keys.decrypt(&account.pickle)?);
+            rotated += sqlx::query!(
+                "UPDATE accounts SET pickle = $1 WHERE id = $2 AND pickle = $3;",
+                pickle,
+                account.id,
+                account.pickle
+            )
+            .execute(&pg)
+            .await?
+            .rows_affected();
+        }
+    }
+}
@@ -0,0 +1,28 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::routing::{get, post};
+
+pub mod pickles;
+pub mod reconcile_counters;
+pub mod rotate_pickles;
+
+pub fn router() -> axum::Router<crate::GSt> {
+    axum::Router::new()
+        .route("/admin/pickles", get(pickles::route))
+        .route("/admin/pickles/rotate", post(rotate_pickles::route))
+        .route("/admin/counters/reconcile", post(reconcile_counters::route))
+}
@@ -0,0 +1,47 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+
+use crate::{auth::get_user, extract::Path};
+
+/// Drops every message in the inbox up to and including `message_id`, once
+/// the device has them safe.
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path((device_id, message_id)): Path<(String, String)>,
+) -> Result<String, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    if !super::device_exists(&state.pg, &actor.id, &device_id).await? {
+        return Err(crate::Error::DeviceNotExist);
+    }
+    let message_id = message_id
+        .parse::<i64>()
+        .map_err(|_| crate::Error::MessageNotExist)?;
+
+    sqlx::query!(
+        "DELETE FROM device_messages WHERE actor_id = $1 AND device_id = $2 AND CAST(id AS BIGINT) <= $3;",
+        actor.id,
+        device_id,
+        message_id
+    )
+    .execute(&state.pg)
+    .await?;
+
+    Ok("".to_string())
+}
@@ -0,0 +1,65 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::OneTimeKey;
+use serde::Deserialize;
+
+use crate::{auth::get_user, extract::Json, routes::users::block_exists};
+
+#[derive(Deserialize)]
+pub struct ClaimTarget {
+    actor_id: String,
+    device_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct ClaimKeys {
+    devices: Vec<ClaimTarget>,
+}
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Json(model): Json<ClaimKeys>,
+) -> Result<Json<Vec<OneTimeKey>>, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    // checked up front so no keys are used up by a request that fails
+    for target in &model.devices {
+        if block_exists(&state.pg, &actor.id, &target.actor_id).await? {
+            return Err(crate::Error::Blocked);
+        }
+    }
+
+    let mut claimed = Vec::new();
+    for target in model.devices {
+        // every key may only ever be handed out once
+        if let Some(key) = sqlx::query_as!(
+            OneTimeKey,
+            "DELETE FROM one_time_keys WHERE (actor_id, device_id, key_id) IN (SELECT actor_id, device_id, key_id FROM one_time_keys WHERE actor_id = $1 AND device_id = $2 LIMIT 1 FOR UPDATE SKIP LOCKED) RETURNING *;",
+            target.actor_id,
+            target.device_id
+        )
+        .fetch_optional(&state.pg)
+        .await?
+        {
+            claimed.push(key);
+        }
+    }
+
+    Ok(Json(claimed))
+}
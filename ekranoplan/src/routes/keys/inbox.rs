@@ -0,0 +1,49 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::DeviceMessage;
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Path},
+};
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(device_id): Path<String>,
+) -> Result<Json<Vec<DeviceMessage>>, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    if !super::device_exists(&state.pg, &actor.id, &device_id).await? {
+        return Err(crate::Error::DeviceNotExist);
+    }
+
+    // messages stay until acked, as ones lost on the way would take the keys
+    // in them along
+    let messages = sqlx::query_as!(
+        DeviceMessage,
+        "SELECT * FROM device_messages WHERE actor_id = $1 AND device_id = $2 ORDER BY CAST(id AS BIGINT) LIMIT $3;",
+        actor.id,
+        device_id,
+        super::INBOX_PAGE
+    )
+    .fetch_all(&state.pg)
+    .await?;
+
+    Ok(Json(messages))
+}
@@ -0,0 +1,82 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::routing::{get, post};
+use sqlx::PgPool;
+use vodozemac::{Ed25519PublicKey, megolm::MegolmMessage};
+
+pub mod ack;
+pub mod claim;
+pub mod inbox;
+pub mod query;
+pub mod send;
+pub mod upload;
+
+pub const MAX_DEVICES: i64 = 10;
+/// Per device, a client should keep about half this many uploaded.
+pub const MAX_ONE_TIME_KEYS: i64 = 100;
+/// How many to-device messages an inbox hands over at once.
+pub const INBOX_PAGE: i64 = 100;
+
+pub async fn device_exists(
+    db: &PgPool,
+    actor_id: &str,
+    device_id: &str,
+) -> Result<bool, crate::Error> {
+    if sqlx::query!(
+        "SELECT id FROM devices WHERE actor_id = $1 AND id = $2",
+        actor_id,
+        device_id
+    )
+    .fetch_optional(db)
+    .await?
+    .is_some()
+    {
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Checks that `content` is a well-formed megolm message sent by one of
+/// `actor_id`'s devices. The server can't decrypt it, only look at its shape.
+pub async fn validate_megolm(
+    db: &PgPool,
+    actor_id: &str,
+    device_id: &str,
+    session_id: &str,
+    content: &str,
+) -> Result<(), crate::Error> {
+    if !device_exists(db, actor_id, device_id).await? {
+        return Err(crate::Error::DeviceNotExist);
+    }
+
+    // megolm session ids are the session's ed25519 key
+    Ed25519PublicKey::from_base64(session_id).map_err(|_| crate::Error::InvalidKeys)?;
+    MegolmMessage::from_base64(content).map_err(|_| crate::Error::InvalidCiphertext)?;
+
+    Ok(())
+}
+
+pub fn router() -> axum::Router<crate::GSt> {
+    axum::Router::new()
+        .route("/keys/upload", post(upload::route))
+        .route("/keys/query", post(query::route))
+        .route("/keys/claim", post(claim::route))
+        .route("/keys/send", post(send::route))
+        .route("/keys/inbox/:device_id", get(inbox::route))
+        .route("/keys/inbox/:device_id/ack/:message_id", post(ack::route))
+}
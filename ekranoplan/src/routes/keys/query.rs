@@ -0,0 +1,53 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use std::collections::HashMap;
+
+use axum::{extract::State, http::HeaderMap};
+use models::Device;
+use serde::Deserialize;
+
+use crate::{auth::get_user, extract::Json};
+
+#[derive(Deserialize)]
+pub struct QueryKeys {
+    actor_ids: Vec<String>,
+}
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Json(model): Json<QueryKeys>,
+) -> Result<Json<HashMap<String, Vec<Device>>>, crate::Error> {
+    get_user(&map, &state.key, &state.pg).await?;
+
+    let devices = sqlx::query_as!(
+        Device,
+        "SELECT * FROM devices WHERE actor_id = ANY($1);",
+        &model.actor_ids
+    )
+    .fetch_all(&state.pg)
+    .await?;
+
+    let mut keys: HashMap<String, Vec<Device>> = HashMap::new();
+    for device in devices {
+        keys.entry(device.actor_id.clone())
+            .or_default()
+            .push(device);
+    }
+
+    Ok(Json(keys))
+}
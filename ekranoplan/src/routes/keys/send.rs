@@ -0,0 +1,98 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::DeviceMessage;
+use serde::Deserialize;
+use vodozemac::olm::OlmMessage;
+
+use crate::{auth::get_user, extract::Json, routes::users::block_exists, utils::send_event};
+
+#[derive(Deserialize)]
+pub struct OutgoingMessage {
+    actor_id: String,
+    device_id: String,
+    r#type: i32,
+    body: String,
+}
+
+#[derive(Deserialize)]
+pub struct SendMessages {
+    device_id: String,
+    messages: Vec<OutgoingMessage>,
+}
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Json(model): Json<SendMessages>,
+) -> Result<String, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    if !super::device_exists(&state.pg, &actor.id, &model.device_id).await? {
+        return Err(crate::Error::DeviceNotExist);
+    }
+
+    for message in &model.messages {
+        let body =
+            vodozemac::base64_decode(&message.body).map_err(|_| crate::Error::InvalidCiphertext)?;
+        OlmMessage::from_parts(message.r#type as usize, &body)
+            .map_err(|_| crate::Error::InvalidCiphertext)?;
+
+        // to-device messages are DMs too
+        if block_exists(&state.pg, &actor.id, &message.actor_id).await? {
+            return Err(crate::Error::Blocked);
+        }
+    }
+
+    let mut tx = state.pg.begin().await?;
+    let mut sent = Vec::new();
+
+    for message in model.messages {
+        if !super::device_exists(&state.pg, &message.actor_id, &message.device_id).await? {
+            return Err(crate::Error::DeviceNotExist);
+        }
+
+        let msg = sqlx::query_as!(
+            DeviceMessage,
+            "INSERT INTO device_messages (id, actor_id, device_id, sender_id, sender_device, type, body) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *;",
+            state.snow.generate().unwrap().to_string(),
+            message.actor_id,
+            message.device_id,
+            actor.id,
+            model.device_id,
+            message.r#type,
+            message.body
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        sent.push(msg);
+    }
+
+    tx.commit().await?;
+
+    for msg in sent {
+        let actor_id = msg.actor_id.clone();
+        send_event(
+            &state.consumants,
+            vec![&actor_id],
+            crate::X15Message::DeviceMessageCreate { msg },
+        )
+        .await;
+    }
+
+    Ok("".to_string())
+}
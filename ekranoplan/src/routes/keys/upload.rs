@@ -0,0 +1,125 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use std::collections::HashMap;
+
+use axum::{extract::State, http::HeaderMap};
+use models::Device;
+use serde::{Deserialize, Serialize};
+use vodozemac::{Curve25519PublicKey, Ed25519PublicKey, Ed25519Signature};
+
+use crate::{auth::get_user, extract::Json};
+
+#[derive(Deserialize)]
+pub struct UploadKeys {
+    device_id: String,
+    identity_key: String,
+    signing_key: String,
+    signature: String,
+    #[serde(default)]
+    one_time_keys: HashMap<String, String>,
+}
+
+#[derive(Serialize)]
+pub struct KeyCounts {
+    one_time_keys: i64,
+}
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Json(model): Json<UploadKeys>,
+) -> Result<Json<KeyCounts>, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    Curve25519PublicKey::from_base64(&model.identity_key).map_err(|_| crate::Error::InvalidKeys)?;
+    let signing_key =
+        Ed25519PublicKey::from_base64(&model.signing_key).map_err(|_| crate::Error::InvalidKeys)?;
+    let signature = Ed25519Signature::from_base64(&model.signature)
+        .map_err(|_| crate::Error::InvalidSignature)?;
+    signing_key
+        .verify(
+            format!("{}:{}:{}", actor.id, model.device_id, model.identity_key).as_bytes(),
+            &signature,
+        )
+        .map_err(|_| crate::Error::InvalidSignature)?;
+
+    for key in model.one_time_keys.values() {
+        Curve25519PublicKey::from_base64(key).map_err(|_| crate::Error::InvalidKeys)?;
+    }
+
+    let mut tx = state.pg.begin().await?;
+
+    // uploads by one actor go one at a time, so they can't all fit under
+    // the limits at once
+    sqlx::query!("SELECT id FROM actors WHERE id = $1 FOR UPDATE;", actor.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    let device = sqlx::query_as!(
+        Device,
+        "INSERT INTO devices (id, actor_id, identity_key, signing_key, signature) VALUES ($1, $2, $3, $4, $5) ON CONFLICT (actor_id, id) DO UPDATE SET signature = EXCLUDED.signature RETURNING *;",
+        model.device_id,
+        actor.id,
+        model.identity_key,
+        model.signing_key,
+        model.signature
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    // a device's identity is fixed for its lifetime, new keys means a new device
+    if device.identity_key != model.identity_key || device.signing_key != model.signing_key {
+        return Err(crate::Error::DeviceKeysChanged);
+    }
+    let devices = sqlx::query!(
+        "SELECT COUNT(id) FROM devices WHERE actor_id = $1;",
+        actor.id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if devices.count.unwrap_or(0) > super::MAX_DEVICES {
+        return Err(crate::Error::TooManyDevices);
+    }
+
+    let (key_ids, keys): (Vec<String>, Vec<String>) = model.one_time_keys.into_iter().unzip();
+    sqlx::query!(
+        "INSERT INTO one_time_keys (actor_id, device_id, key_id, key) SELECT $1, $2, UNNEST($3::TEXT[]), UNNEST($4::TEXT[]) ON CONFLICT DO NOTHING;",
+        actor.id,
+        device.id,
+        &key_ids,
+        &keys
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let count = sqlx::query!(
+        "SELECT COUNT(key_id) FROM one_time_keys WHERE actor_id = $1 AND device_id = $2;",
+        actor.id,
+        device.id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if count.count.unwrap_or(0) > super::MAX_ONE_TIME_KEYS {
+        return Err(crate::Error::TooManyKeys);
+    }
+
+    tx.commit().await?;
+
+    Ok(Json(KeyCounts {
+        one_time_keys: count.count.unwrap_or(0),
+    }))
+}
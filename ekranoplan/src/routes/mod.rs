@@ -14,6 +14,8 @@
    limitations under the License.
 */
 
+pub mod admin;
+pub mod keys;
 pub mod rooms;
 pub mod tracks;
 pub mod users;
@@ -21,6 +23,8 @@ pub mod x15;
 
 pub fn router() -> axum::Router<crate::GSt> {
     axum::Router::new()
+        .merge(admin::router())
+        .merge(keys::router())
         .merge(rooms::router())
         .merge(users::router())
         .merge(tracks::router())
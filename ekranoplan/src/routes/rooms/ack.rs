@@ -0,0 +1,91 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::ReadState;
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Path},
+    utils::send_event,
+};
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path((room_id, message_id)): Path<(String, String)>,
+) -> Result<Json<ReadState>, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    if !super::member_exists(&state.pg, &room_id, &actor.id).await? {
+        return Err(crate::Error::RoomNotExist);
+    }
+
+    let msg = sqlx::query!(
+        "SELECT id FROM messages WHERE id = $1 AND room_id = $2;",
+        message_id,
+        room_id
+    )
+    .fetch_optional(&state.pg)
+    .await?;
+
+    if msg.is_none() {
+        return Err(crate::Error::MessageNotExist);
+    }
+
+    // only ever move the read marker forward, leaving mentions which come
+    // after it counted the way create_message counts them
+    let read_state = sqlx::query_as!(
+        ReadState,
+        "INSERT INTO read_states (user_id, room_id, last_message_id, mentions)
+        VALUES ($1, $2, $3, (
+            SELECT COUNT(*)::INTEGER FROM messages
+            WHERE room_id = $2 AND CAST(id AS BIGINT) > CAST($3::TEXT AS BIGINT) AND author_id != $1 AND session_id IS NULL
+            AND POSITION('<@' || $1::TEXT || '>' IN content) > 0
+            AND author_id NOT IN (SELECT muted_id FROM mutes WHERE muter_id = $1)
+        ))
+        ON CONFLICT (user_id, room_id) DO UPDATE SET last_message_id = EXCLUDED.last_message_id, mentions = EXCLUDED.mentions
+        WHERE read_states.last_message_id IS NULL OR CAST(read_states.last_message_id AS BIGINT) < CAST(EXCLUDED.last_message_id AS BIGINT) RETURNING *;",
+        actor.id,
+        room_id,
+        message_id
+    )
+    .fetch_optional(&state.pg)
+    .await?;
+
+    if let Some(read_state) = read_state {
+        send_event(
+            &state.consumants,
+            vec![&actor.id],
+            crate::X15Message::ReadStateUpdate {
+                state: read_state.clone(),
+            },
+        )
+        .await;
+        Ok(Json(read_state))
+    } else {
+        Ok(Json(
+            sqlx::query_as!(
+                ReadState,
+                "SELECT * FROM read_states WHERE user_id = $1 AND room_id = $2;",
+                actor.id,
+                room_id
+            )
+            .fetch_one(&state.pg)
+            .await?,
+        ))
+    }
+}
@@ -0,0 +1,90 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::{Actor, Channel};
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Path},
+    utils::{get_channel, send_event},
+};
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path((room_id, actor_id)): Path<(String, String)>,
+) -> Result<Json<Channel>, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    super::get_group(&state.pg, &room_id, &actor.id).await?;
+
+    // counted under the room's lock, so adds at the same time can't all fit
+    // in the last place
+    let mut tx = state.pg.begin().await?;
+    let room = super::lock_room(&mut tx, &room_id).await?;
+    if room.owner_id.as_ref() != Some(&actor.id) {
+        return Err(crate::Error::NotRoomOwner);
+    }
+
+    let members = super::member_ids(&mut *tx, &room_id).await?;
+    if members.contains(&actor_id) {
+        return Err(crate::Error::MemberExists);
+    }
+    if members.len() >= super::MAX_GROUP_MEMBERS {
+        return Err(crate::Error::RoomFull);
+    }
+
+    let new_member = sqlx::query_as!(Actor, "SELECT * FROM actors WHERE id = $1;", actor_id)
+        .fetch_optional(&state.pg)
+        .await?
+        .ok_or(crate::Error::UserNotFound)?;
+    if crate::routes::users::block_exists(&state.pg, &actor.id, &new_member.id).await? {
+        return Err(crate::Error::Blocked);
+    }
+
+    sqlx::query!(
+        "INSERT INTO room_members (room_id, actor_id) VALUES ($1, $2);",
+        room_id,
+        new_member.id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    send_event(
+        &state.consumants,
+        members.iter().map(|m| m.as_str()).collect(),
+        crate::X15Message::MemberAdd {
+            room_id: room_id.clone(),
+            actor: new_member.clone(),
+        },
+    )
+    .await;
+
+    let channel = get_channel(&state.pg, room, None).await?;
+    send_event(
+        &state.consumants,
+        vec![&new_member.id],
+        crate::X15Message::RoomCreate {
+            room: channel.room.clone(),
+            members: channel.members.clone(),
+        },
+    )
+    .await;
+
+    Ok(Json(channel))
+}
@@ -0,0 +1,114 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::{Channel, Room};
+use serde::Deserialize;
+use serde_valid::Validate;
+
+use crate::{
+    auth::get_user,
+    extract::Json,
+    utils::{get_channel, send_event},
+    validation::ValidJson,
+};
+
+#[derive(Deserialize, Validate)]
+pub struct CreateRoom {
+    #[serde(default)]
+    #[validate(min_length = 1)]
+    #[validate(max_length = 64)]
+    name: Option<String>,
+    members: Vec<String>,
+}
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    ValidJson(model): ValidJson<CreateRoom>,
+) -> Result<Json<Channel>, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    let mut member_ids = vec![actor.id.clone()];
+    for member_id in model.members {
+        if !member_ids.contains(&member_id) {
+            member_ids.push(member_id);
+        }
+    }
+
+    if member_ids.len() > super::MAX_GROUP_MEMBERS {
+        return Err(crate::Error::RoomFull);
+    }
+
+    let found = sqlx::query!(
+        "SELECT COUNT(id) FROM actors WHERE id = ANY($1);",
+        &member_ids
+    )
+    .fetch_one(&state.pg)
+    .await?;
+    if found.count.unwrap_or(0) != member_ids.len() as i64 {
+        return Err(crate::Error::UserNotFound);
+    }
+
+    let blocked = sqlx::query!(
+        "SELECT since FROM blocks WHERE (blocker_id = $1 AND blocked_id = ANY($2)) OR (blocker_id = ANY($2) AND blocked_id = $1);",
+        actor.id,
+        &member_ids
+    )
+    .fetch_optional(&state.pg)
+    .await?;
+    if blocked.is_some() {
+        return Err(crate::Error::Blocked);
+    }
+
+    let room_id = state.snow.generate().unwrap().to_string();
+
+    let mut tx = state.pg.begin().await?;
+
+    let room = sqlx::query_as!(
+        Room,
+        "INSERT INTO rooms (id, name, type, owner_id) VALUES ($1, $2, $3, $4) RETURNING *;",
+        room_id,
+        model.name,
+        super::GROUP_ROOM,
+        actor.id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "INSERT INTO room_members (room_id, actor_id) SELECT $1, UNNEST($2::TEXT[]);",
+        room_id,
+        &member_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let channel = get_channel(&state.pg, room, Some(&actor)).await?;
+
+    send_event(
+        &state.consumants,
+        member_ids.iter().map(|m| m.as_str()).collect(),
+        crate::X15Message::RoomCreate {
+            room: channel.room.clone(),
+            members: channel.members.clone(),
+        },
+    )
+    .await;
+
+    Ok(Json(channel))
+}
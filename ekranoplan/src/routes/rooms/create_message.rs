@@ -0,0 +1,91 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::Message;
+use serde::Deserialize;
+use serde_valid::Validate;
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Path},
+    routes::keys::validate_megolm,
+    utils::{create_message, send_event},
+    validation::{self, ValidJson},
+};
+
+#[derive(Deserialize, Validate)]
+pub struct CreateMessage {
+    /// Long enough for encrypted messages, which are base64 with megolm's
+    /// overhead on top.
+    #[validate(max_length = 4000)]
+    #[validate(custom = validation::not_blank)]
+    content: String,
+    #[serde(default)]
+    session_id: Option<String>,
+    #[serde(default)]
+    device_id: Option<String>,
+}
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(room_id): Path<String>,
+    ValidJson(model): ValidJson<CreateMessage>,
+) -> Result<Json<Message>, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    if !super::member_exists(&state.pg, &room_id, &actor.id).await? {
+        return Err(crate::Error::RoomNotExist);
+    }
+    if super::dm_blocked(&state.pg, &room_id, &actor.id).await? {
+        return Err(crate::Error::Blocked);
+    }
+
+    match (&model.session_id, &model.device_id) {
+        (Some(session_id), Some(device_id)) => {
+            validate_megolm(&state.pg, &actor.id, device_id, session_id, &model.content).await?
+        }
+        (None, None) => {}
+        _ => return Err(crate::Error::InvalidCiphertext),
+    }
+
+    let members = super::member_ids(&state.pg, &room_id).await?;
+    let mut tx = state.pg.begin().await?;
+    let msg = create_message(
+        &mut tx,
+        &state.snow,
+        &room_id,
+        &actor.id,
+        &model.content,
+        model.session_id.as_deref(),
+        model.device_id.as_deref(),
+    )
+    .await?;
+    tx.commit().await?;
+
+    send_event(
+        &state.consumants,
+        members.iter().map(|m| m.as_str()).collect(),
+        crate::X15Message::MessageCreate {
+            room_id,
+            msg: msg.clone(),
+        },
+    )
+    .await;
+
+    Ok(Json(msg))
+}
@@ -0,0 +1,69 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+
+use crate::{auth::get_user, extract::Path, utils::send_event};
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path((room_id, message_id)): Path<(String, String)>,
+) -> Result<String, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    if !super::member_exists(&state.pg, &room_id, &actor.id).await? {
+        return Err(crate::Error::RoomNotExist);
+    }
+
+    let mut tx = state.pg.begin().await?;
+
+    let msg = sqlx::query!(
+        "DELETE FROM messages WHERE id = $1 AND room_id = $2 AND author_id = $3 RETURNING id;",
+        message_id,
+        room_id,
+        actor.id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if msg.is_none() {
+        return Err(crate::Error::MessageNotExist);
+    }
+
+    // point the room back at whichever message is now the latest
+    sqlx::query!(
+        "UPDATE rooms SET last_message_id = (SELECT id FROM messages WHERE room_id = $1 ORDER BY timestamp DESC LIMIT 1) WHERE id = $1;",
+        room_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let members = super::member_ids(&state.pg, &room_id).await?;
+    tx.commit().await?;
+
+    send_event(
+        &state.consumants,
+        members.iter().map(|m| m.as_str()).collect(),
+        crate::X15Message::MessageDelete {
+            room_id,
+            message_id,
+        },
+    )
+    .await;
+
+    Ok("".to_string())
+}
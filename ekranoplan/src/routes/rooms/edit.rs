@@ -0,0 +1,72 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::Room;
+use serde::Deserialize;
+use serde_valid::Validate;
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Path},
+    utils::send_event,
+    validation::{self, ValidJson},
+};
+
+#[derive(Deserialize, Validate)]
+pub struct EditRoom {
+    /// Set to null to clear the name.
+    #[serde(default, deserialize_with = "validation::nullable")]
+    #[validate(min_length = 1)]
+    #[validate(max_length = 64)]
+    name: Option<Option<String>>,
+}
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(room_id): Path<String>,
+    ValidJson(model): ValidJson<EditRoom>,
+) -> Result<Json<Room>, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    let mut room = super::get_group(&state.pg, &room_id, &actor.id).await?;
+
+    if room.owner_id.as_ref() != Some(&actor.id) {
+        return Err(crate::Error::NotRoomOwner);
+    }
+
+    if let Some(name) = model.name {
+        room = sqlx::query_as!(
+            Room,
+            "UPDATE rooms SET name = $1 WHERE id = $2 RETURNING *;",
+            name,
+            room_id
+        )
+        .fetch_one(&state.pg)
+        .await?;
+
+        let members = super::member_ids(&state.pg, &room_id).await?;
+        send_event(
+            &state.consumants,
+            members.iter().map(|m| m.as_str()).collect(),
+            crate::X15Message::RoomUpdate { room: room.clone() },
+        )
+        .await;
+    }
+
+    Ok(Json(room))
+}
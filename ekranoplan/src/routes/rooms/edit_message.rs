@@ -0,0 +1,104 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::Message;
+use serde::Deserialize;
+use serde_valid::Validate;
+use sqlx::types::chrono;
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Path},
+    routes::keys::validate_megolm,
+    utils::send_event,
+    validation::{self, ValidJson},
+};
+
+#[derive(Deserialize, Validate)]
+pub struct EditMessage {
+    /// Long enough for encrypted messages, which are base64 with megolm's
+    /// overhead on top.
+    #[validate(max_length = 4000)]
+    #[validate(custom = validation::not_blank)]
+    content: String,
+    #[serde(default)]
+    session_id: Option<String>,
+}
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path((room_id, message_id)): Path<(String, String)>,
+    ValidJson(model): ValidJson<EditMessage>,
+) -> Result<Json<Message>, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    if !super::member_exists(&state.pg, &room_id, &actor.id).await? {
+        return Err(crate::Error::RoomNotExist);
+    }
+    if super::dm_blocked(&state.pg, &room_id, &actor.id).await? {
+        return Err(crate::Error::Blocked);
+    }
+
+    let original = sqlx::query_as!(
+        Message,
+        "SELECT * FROM messages WHERE id = $1 AND room_id = $2 AND author_id = $3;",
+        message_id,
+        room_id,
+        actor.id
+    )
+    .fetch_optional(&state.pg)
+    .await?;
+
+    if let Some(original) = original {
+        // encrypted messages stay encrypted, and by the same device
+        match (&original.device_id, &model.session_id) {
+            (Some(device_id), Some(session_id)) => {
+                validate_megolm(&state.pg, &actor.id, device_id, session_id, &model.content).await?
+            }
+            (None, None) => {}
+            _ => return Err(crate::Error::InvalidCiphertext),
+        }
+
+        let ts = chrono::Utc::now().timestamp_millis();
+        let msg = sqlx::query_as!(
+            Message,
+            "UPDATE messages SET content = $1, session_id = $2, edited_timestamp = $3 WHERE id = $4 RETURNING *;",
+            model.content,
+            model.session_id,
+            ts,
+            original.id
+        )
+        .fetch_one(&state.pg)
+        .await?;
+
+        let members = super::member_ids(&state.pg, &room_id).await?;
+        send_event(
+            &state.consumants,
+            members.iter().map(|m| m.as_str()).collect(),
+            crate::X15Message::MessageUpdate {
+                room_id,
+                msg: msg.clone(),
+            },
+        )
+        .await;
+
+        Ok(Json(msg))
+    } else {
+        Err(crate::Error::MessageNotExist)
+    }
+}
@@ -0,0 +1,42 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::{Channel, Room};
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Path},
+    utils::get_channel,
+};
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(room_id): Path<String>,
+) -> Result<Json<Channel>, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    if !super::member_exists(&state.pg, &room_id, &actor.id).await? {
+        return Err(crate::Error::RoomNotExist);
+    }
+
+    let room = sqlx::query_as!(Room, "SELECT * FROM rooms WHERE id = $1;", room_id)
+        .fetch_one(&state.pg)
+        .await?;
+
+    Ok(Json(get_channel(&state.pg, room, Some(&actor)).await?))
+}
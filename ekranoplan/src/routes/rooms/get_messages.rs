@@ -0,0 +1,120 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::Message;
+use serde::Deserialize;
+use sqlx::PgPool;
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Path, Query},
+};
+
+static DEFAULT_LIMIT: i64 = 50;
+static MAX_LIMIT: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct HistoryOptions {
+    #[serde(default)]
+    before: Option<i64>,
+    #[serde(default)]
+    after: Option<i64>,
+    #[serde(default)]
+    around: Option<i64>,
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+/// Messages with an id below `before`, newest-first.
+async fn older(
+    pg: &PgPool,
+    room_id: &str,
+    before: i64,
+    limit: i64,
+) -> Result<Vec<Message>, crate::Error> {
+    Ok(sqlx::query_as!(
+        Message,
+        "SELECT * FROM messages WHERE room_id = $1 AND CAST(id AS BIGINT) < $2 ORDER BY CAST(id AS BIGINT) DESC LIMIT $3;",
+        room_id,
+        before,
+        limit
+    )
+    .fetch_all(pg)
+    .await?)
+}
+
+/// Messages with an id above `after`, newest-first.
+async fn newer(
+    pg: &PgPool,
+    room_id: &str,
+    after: i64,
+    limit: i64,
+) -> Result<Vec<Message>, crate::Error> {
+    let mut messages = sqlx::query_as!(
+        Message,
+        "SELECT * FROM messages WHERE room_id = $1 AND CAST(id AS BIGINT) > $2 ORDER BY CAST(id AS BIGINT) ASC LIMIT $3;",
+        room_id,
+        after,
+        limit
+    )
+    .fetch_all(pg)
+    .await?;
+    messages.reverse();
+    Ok(messages)
+}
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(room_id): Path<String>,
+    Query(options): Query<HistoryOptions>,
+) -> Result<Json<Vec<Message>>, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    if !super::member_exists(&state.pg, &room_id, &actor.id).await? {
+        return Err(crate::Error::RoomNotExist);
+    }
+
+    let limit = options.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let messages = if let Some(around) = options.around {
+        // the cursor message itself is included on the older side
+        let mut messages = newer(&state.pg, &room_id, around, limit / 2).await?;
+        messages.extend(
+            older(
+                &state.pg,
+                &room_id,
+                around.saturating_add(1),
+                limit - limit / 2,
+            )
+            .await?,
+        );
+        messages
+    } else if let Some(after) = options.after {
+        newer(&state.pg, &room_id, after, limit).await?
+    } else {
+        older(
+            &state.pg,
+            &room_id,
+            options.before.unwrap_or(i64::MAX),
+            limit,
+        )
+        .await?
+    };
+
+    Ok(Json(messages))
+}
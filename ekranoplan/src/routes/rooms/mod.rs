@@ -14,6 +14,124 @@
    limitations under the License.
 */
 
+use axum::routing::{get, patch, post, put};
+use models::Room;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+
+pub mod ack;
+pub mod add_member;
+pub mod create;
+pub mod create_message;
+pub mod delete_message;
+pub mod edit;
+pub mod edit_message;
+pub mod get_all;
+pub mod get_channel;
+pub mod get_messages;
+pub mod remove_member;
+
+pub const DM_ROOM: i32 = 0;
+pub const GROUP_ROOM: i32 = 1;
+pub const MAX_GROUP_MEMBERS: usize = 10;
+
+pub async fn member_exists(
+    db: &PgPool,
+    room_id: &str,
+    actor_id: &str,
+) -> Result<bool, crate::Error> {
+    if sqlx::query!(
+        "SELECT actor_id FROM room_members WHERE room_id = $1 AND actor_id = $2",
+        room_id,
+        actor_id
+    )
+    .fetch_optional(db)
+    .await?
+    .is_some()
+    {
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Whether `room_id` is a DM whose other member has a block with
+/// `actor_id` in either direction, in which case it can't be written to.
+pub async fn dm_blocked(db: &PgPool, room_id: &str, actor_id: &str) -> Result<bool, crate::Error> {
+    Ok(sqlx::query!(
+        "SELECT room_members.actor_id FROM rooms JOIN room_members ON room_members.room_id = rooms.id WHERE rooms.id = $1 AND rooms.type = $2 AND room_members.actor_id != $3 AND EXISTS (SELECT 1 FROM blocks WHERE (blocker_id = $3 AND blocked_id = room_members.actor_id) OR (blocker_id = room_members.actor_id AND blocked_id = $3));",
+        room_id,
+        DM_ROOM,
+        actor_id
+    )
+    .fetch_optional(db)
+    .await?
+    .is_some())
+}
+
+pub async fn member_ids(
+    db: impl PgExecutor<'_>,
+    room_id: &str,
+) -> Result<Vec<String>, crate::Error> {
+    Ok(sqlx::query!(
+        "SELECT actor_id FROM room_members WHERE room_id = $1",
+        room_id
+    )
+    .fetch_all(db)
+    .await?
+    .into_iter()
+    .map(|m| m.actor_id)
+    .collect())
+}
+
+/// Locks `room_id` until the end of the transaction, so changes to its
+/// members made at the same time happen one after the other.
+pub async fn lock_room(conn: &mut PgConnection, room_id: &str) -> Result<Room, crate::Error> {
+    sqlx::query_as!(
+        Room,
+        "SELECT * FROM rooms WHERE id = $1 FOR UPDATE;",
+        room_id
+    )
+    .fetch_optional(conn)
+    .await?
+    .ok_or(crate::Error::RoomNotExist)
+}
+
+/// Fetches a group room `actor_id` is a member of.
+pub async fn get_group(db: &PgPool, room_id: &str, actor_id: &str) -> Result<Room, crate::Error> {
+    if !member_exists(db, room_id, actor_id).await? {
+        return Err(crate::Error::RoomNotExist);
+    }
+
+    let room = sqlx::query_as!(Room, "SELECT * FROM rooms WHERE id = $1;", room_id)
+        .fetch_one(db)
+        .await?;
+
+    if room.r#type != GROUP_ROOM {
+        Err(crate::Error::RoomNotGroup)
+    } else {
+        Ok(room)
+    }
+}
+
 pub fn router() -> axum::Router<crate::GSt> {
     axum::Router::new()
+        .route("/users/@me/rooms", get(get_all::route))
+        .route("/rooms", post(create::route))
+        .route(
+            "/rooms/:room_id",
+            get(get_channel::route).patch(edit::route),
+        )
+        .route(
+            "/rooms/:room_id/members/:actor_id",
+            put(add_member::route).delete(remove_member::route),
+        )
+        .route(
+            "/rooms/:room_id/messages",
+            get(get_messages::route).post(create_message::route),
+        )
+        .route(
+            "/rooms/:room_id/messages/:message_id",
+            patch(edit_message::route).delete(delete_message::route),
+        )
+        .route("/rooms/:room_id/messages/:message_id/ack", post(ack::route))
 }
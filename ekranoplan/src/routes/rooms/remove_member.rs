@@ -0,0 +1,105 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::Room;
+
+use crate::{auth::get_user, extract::Path, utils::send_event};
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path((room_id, mut actor_id)): Path<(String, String)>,
+) -> Result<String, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    let room = super::get_group(&state.pg, &room_id, &actor.id).await?;
+
+    // removing yourself is leaving, which anyone may do
+    if actor_id == "@me" {
+        actor_id = actor.id.clone();
+    } else if actor_id != actor.id && room.owner_id.as_ref() != Some(&actor.id) {
+        return Err(crate::Error::NotRoomOwner);
+    }
+
+    let members = super::member_ids(&state.pg, &room_id).await?;
+    if !members.contains(&actor_id) {
+        return Err(crate::Error::MemberNotExist);
+    }
+
+    let mut tx = state.pg.begin().await?;
+
+    sqlx::query!(
+        "DELETE FROM room_members WHERE room_id = $1 AND actor_id = $2;",
+        room_id,
+        actor_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM read_states WHERE room_id = $1 AND user_id = $2;",
+        room_id,
+        actor_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // hand the room over to whoever has been in it longest if its owner left
+    let new_room = if members.len() == 1 {
+        sqlx::query!("DELETE FROM rooms WHERE id = $1;", room_id)
+            .execute(&mut *tx)
+            .await?;
+        None
+    } else if room.owner_id.as_ref() == Some(&actor_id) {
+        Some(
+            sqlx::query_as!(
+                Room,
+                "UPDATE rooms SET owner_id = (SELECT actor_id FROM room_members WHERE room_id = $1 ORDER BY joined_at, actor_id LIMIT 1) WHERE id = $1 RETURNING *;",
+                room_id
+            )
+            .fetch_one(&mut *tx)
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    tx.commit().await?;
+
+    send_event(
+        &state.consumants,
+        members.iter().map(|m| m.as_str()).collect(),
+        crate::X15Message::MemberRemove {
+            room_id,
+            actor_id: actor_id.clone(),
+        },
+    )
+    .await;
+    if let Some(room) = new_room {
+        send_event(
+            &state.consumants,
+            members
+                .iter()
+                .filter(|m| **m != actor_id)
+                .map(|m| m.as_str())
+                .collect(),
+            crate::X15Message::RoomUpdate { room },
+        )
+        .await;
+    }
+
+    Ok("".to_string())
+}
@@ -14,13 +14,14 @@
    limitations under the License.
 */
 
-use axum::{
-    extract::{Path, State},
-    http::HeaderMap,
-};
+use axum::{extract::State, http::HeaderMap};
 use sqlx::types::chrono;
 
-use crate::auth::get_user;
+use crate::{
+    auth::get_user,
+    counters::{TrackDelta, bump_track},
+    extract::Path,
+};
 
 pub async fn route(
     map: HeaderMap,
@@ -34,15 +35,28 @@ pub async fn route(
         .await?;
 
     if let Some(post) = post {
+        super::check_visible(&state.pg, &post.id, &actor.id).await?;
+
         let time = chrono::Utc::now().timestamp_millis();
+        let mut tx = state.pg.begin().await?;
         sqlx::query!(
             "INSERT INTO track_bookmarks (track_id, user_id, at) VALUES ($1, $2, $3);",
             post.id,
             actor.id,
             time
         )
-        .execute(&state.pg)
+        .execute(&mut *tx)
+        .await?;
+        bump_track(
+            &mut tx,
+            &post.id,
+            TrackDelta {
+                bookmarks: 1,
+                ..Default::default()
+            },
+        )
         .await?;
+        tx.commit().await?;
         Ok("".to_string())
     } else {
         Err(crate::Error::TrackNotExist)
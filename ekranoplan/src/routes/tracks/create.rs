@@ -14,49 +14,91 @@
    limitations under the License.
 */
 
-use axum::{Json, extract::State, http::HeaderMap};
-use models::Track;
+use axum::{extract::State, http::HeaderMap};
+use models::{Track, signing::SIGNATURE_VERSION};
 use serde::Deserialize;
+use serde_valid::Validate;
 use sqlx::types::chrono;
 
-use crate::auth::get_user;
+use crate::{
+    auth::get_user,
+    counters::count_track,
+    extract::Json,
+    validation::{self, ValidJson},
+};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct CreatePost {
+    #[validate(max_length = 1000)]
+    #[validate(custom = validation::not_blank)]
     content: String,
     #[serde(default)]
     parent_id: Option<String>,
+    /// Quotes this track, commenting on it.
+    #[serde(default)]
+    quote_id: Option<String>,
+    #[serde(default)]
+    #[validate(custom = super::visibility)]
+    visibility: i32,
+    #[serde(default)]
+    #[validate(custom = super::reply_policy)]
+    reply_policy: i32,
 }
 
 pub async fn route(
     map: HeaderMap,
     State(state): State<crate::GSt>,
-    Json(model): Json<CreatePost>,
+    ValidJson(model): ValidJson<CreatePost>,
 ) -> Result<Json<Track>, crate::Error> {
     let (actor, account) = get_user(&map, &state.key, &state.pg).await?;
 
-    let pickle =
-        vodozemac::olm::AccountPickle::from_encrypted(&account.pickle, &crate::PICKLE_KEY)?;
-    let acc = vodozemac::olm::Account::from_pickle(pickle);
+    if let Some(ref parent_id) = model.parent_id {
+        super::check_visible(&state.pg, parent_id, &actor.id).await?;
+        super::check_blocked(&state.pg, parent_id, &actor.id).await?;
+        super::check_reply(&state.pg, parent_id, &actor.id).await?;
+    }
+
+    let acc = state.pickles.decrypt(&account.pickle)?;
 
     let id = state.snow.generate().unwrap().to_string();
     let ts = chrono::Utc::now().timestamp_millis();
 
-    let sig_fmt = format!("{}{}{}{}", &id, &actor.id, &ts, &model.content);
+    let (kind, reference_id) = if let Some(ref quote_id) = model.quote_id {
+        let reference_id = super::reference_target(&state.pg, quote_id).await?;
+        super::check_visible(&state.pg, &reference_id, &actor.id).await?;
+        super::check_blocked(&state.pg, &reference_id, &actor.id).await?;
+        (super::QUOTE_TRACK, Some(reference_id))
+    } else {
+        (super::THREAD_TRACK, None)
+    };
+
+    let sig_fmt =
+        super::signing_payload(&id, &actor.id, ts, reference_id.as_deref(), &model.content);
 
     let sig = acc.sign(sig_fmt).to_base64();
 
-    // TODO: verify post id and return a prompt error
-    Ok(Json(sqlx::query_as!(
+    let mut tx = state.pg.begin().await?;
+
+    let track = sqlx::query_as!(
         Track,
-        "INSERT INTO tracks (id, type, author_id, content, original_ts, indexed_ts, parent_id, signature) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING *;",
+        "INSERT INTO tracks (id, type, author_id, content, original_ts, indexed_ts, parent_id, signature, reference_id, signature_version, visibility, reply_policy) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) RETURNING *;",
         id,
-        0,
+        kind,
         actor.id,
         &model.content,
         &ts,
         &ts,
         model.parent_id,
-        sig
-    ).fetch_one(&state.pg).await?))
+        sig,
+        reference_id,
+        SIGNATURE_VERSION,
+        model.visibility,
+        model.reply_policy
+    ).fetch_one(&mut *tx).await?;
+    count_track(&mut tx, &track, 1).await?;
+    super::save_mentions(&mut tx, &track.id, &track.content).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(track))
 }
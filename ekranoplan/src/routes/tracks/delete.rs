@@ -14,12 +14,13 @@
    limitations under the License.
 */
 
-use axum::{
-    extract::{Path, State},
-    http::HeaderMap,
-};
+use axum::{extract::State, http::HeaderMap};
 
-use crate::auth::get_user;
+use crate::{
+    auth::get_user,
+    counters::{ActorDelta, bump_actor},
+    extract::Path,
+};
 
 pub async fn route(
     map: HeaderMap,
@@ -28,9 +29,34 @@ pub async fn route(
 ) -> Result<String, crate::Error> {
     let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
 
-    let post = sqlx::query!("UPDATE tracks SET author_id = NULL, content = $1 WHERE id = $2 AND author_id = $3 RETURNING id", "", track_id, actor.id).fetch_optional(&state.pg).await?;
+    let mut tx = state.pg.begin().await?;
+
+    let post = sqlx::query!("UPDATE tracks SET author_id = NULL, content = $1 WHERE id = $2 AND author_id = $3 RETURNING parent_id", "", track_id, actor.id).fetch_optional(&mut *tx).await?;
+
+    if let Some(post) = post {
+        // the track stays in place for its replies, but stops counting
+        // towards its former author
+        if post.parent_id.is_none() {
+            bump_actor(
+                &mut tx,
+                &actor.id,
+                ActorDelta {
+                    tracks: -1,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+
+        // earlier versions go along with the content
+        sqlx::query!("DELETE FROM track_revisions WHERE track_id = $1;", track_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!("DELETE FROM track_mentions WHERE track_id = $1;", track_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
 
-    if post.is_some() {
         Ok("".to_string())
     } else {
         Err(crate::Error::TrackNotExist)
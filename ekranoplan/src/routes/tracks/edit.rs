@@ -0,0 +1,102 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::{Track, signing::SIGNATURE_VERSION};
+use serde::Deserialize;
+use serde_valid::Validate;
+use sqlx::types::chrono;
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Path},
+    validation::{self, ValidJson},
+};
+
+#[derive(Deserialize, Validate)]
+pub struct EditTrack {
+    #[validate(max_length = 1000)]
+    #[validate(custom = validation::not_blank)]
+    content: String,
+}
+
+/// Replaces the content of a track, keeping the previous version and its
+/// signature as a revision.
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(track_id): Path<String>,
+    ValidJson(model): ValidJson<EditTrack>,
+) -> Result<Json<Track>, crate::Error> {
+    let (actor, account) = get_user(&map, &state.key, &state.pg).await?;
+
+    let mut tx = state.pg.begin().await?;
+
+    let track = sqlx::query_as!(
+        Track,
+        "SELECT * FROM tracks WHERE id = $1 AND author_id = $2 FOR UPDATE;",
+        track_id,
+        actor.id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::TrackNotExist)?;
+
+    if track.r#type == super::REPOST_TRACK {
+        return Err(crate::Error::RepostNotEditable);
+    }
+
+    sqlx::query!(
+        "INSERT INTO track_revisions (track_id, content, signature, ts, signature_version) VALUES ($1, $2, $3, $4, $5);",
+        &track.id,
+        &track.content,
+        &track.signature,
+        track.edited_ts.unwrap_or(track.original_ts),
+        track.signature_version
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let acc = state.pickles.decrypt(&account.pickle)?;
+
+    // edits are signed over when they were made, like a new track would be
+    let ts = chrono::Utc::now().timestamp_millis();
+    let sig_fmt = super::signing_payload(
+        &track.id,
+        &actor.id,
+        ts,
+        track.reference_id.as_deref(),
+        &model.content,
+    );
+    let sig = acc.sign(sig_fmt).to_base64();
+
+    let track = sqlx::query_as!(
+        Track,
+        "UPDATE tracks SET content = $1, signature = $2, edited_ts = $3, signature_version = $4 WHERE id = $5 RETURNING *;",
+        &model.content,
+        sig,
+        ts,
+        SIGNATURE_VERSION,
+        &track.id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    super::save_mentions(&mut tx, &track.id, &track.content).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(track))
+}
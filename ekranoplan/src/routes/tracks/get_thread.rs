@@ -14,19 +14,33 @@
    limitations under the License.
 */
 
-use axum::{
-    Json,
-    extract::{Path, State},
-    http::HeaderMap,
-};
+use axum::{extract::State, http::HeaderMap};
 use models::{Thread, Track};
+use serde::Deserialize;
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Path, Query},
+    utils::{ReplySort, TreeOptions, get_thread_tree},
+};
 
-use crate::{auth::get_user, utils::get_thread};
+#[derive(Deserialize)]
+pub struct ThreadOptions {
+    #[serde(default)]
+    depth: Option<i32>,
+    #[serde(default)]
+    limit: Option<i64>,
+    #[serde(default)]
+    offset: Option<i64>,
+    #[serde(default)]
+    sort: ReplySort,
+}
 
 pub async fn route(
     map: HeaderMap,
     State(state): State<crate::GSt>,
     Path(thread_id): Path<String>,
+    Query(options): Query<ThreadOptions>,
 ) -> Result<Json<Thread>, crate::Error> {
     let user = if map.contains_key("authorization") {
         let (user, _) = get_user(&map, &state.key, &state.pg).await?;
@@ -35,12 +49,25 @@ pub async fn route(
         None
     };
 
-    let post = sqlx::query_as!(Track, "SELECT * FROM tracks WHERE id = $1", thread_id)
-        .fetch_optional(&state.pg)
-        .await?;
+    let post = sqlx::query_as!(
+        Track,
+        "SELECT * FROM tracks WHERE id = $1 AND track_viewable(tracks, $2);",
+        thread_id,
+        user.as_ref().map(|u| u.id.clone())
+    )
+    .fetch_optional(&state.pg)
+    .await?;
 
     if let Some(post) = post {
-        Ok(Json(get_thread(&state.pg, post, true, &user).await?))
+        let options = TreeOptions {
+            depth: options.depth.unwrap_or(1).clamp(1, 8),
+            limit: options.limit.unwrap_or(20).clamp(1, 100),
+            offset: options.offset.unwrap_or(0).max(0),
+            sort: options.sort,
+        };
+        Ok(Json(
+            get_thread_tree(&state.pg, post, options, &user).await?,
+        ))
     } else {
         Err(crate::Error::TrackNotExist)
     }
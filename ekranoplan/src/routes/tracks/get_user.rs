@@ -14,42 +14,41 @@
    limitations under the License.
 */
 
-use axum::{
-    Json,
-    extract::{Path, State},
-    http::HeaderMap,
-};
+use axum::{extract::State, http::HeaderMap};
 use models::{Thread, Track};
 
-use crate::{auth::get_user, utils::get_thread};
+use crate::{
+    auth::get_user,
+    extract::{Json, Path},
+    utils::get_threads,
+};
 
 pub async fn route(
     map: HeaderMap,
     State(state): State<crate::GSt>,
-    Path(mut other_user): Path<String>,
+    Path(other_user): Path<String>,
 ) -> Result<Json<Vec<Thread>>, crate::Error> {
-    let user = if map.contains_key("authorization") && other_user == "@me" {
+    let author = crate::routes::users::find_actor(&state, &map, &other_user)
+        .await?
+        .ok_or(crate::Error::UserNotFound)?;
+    let user = if map.contains_key("authorization") {
         let (user, _) = get_user(&map, &state.key, &state.pg).await?;
-        other_user = user.id.clone();
         Some(user)
     } else {
         None
     };
+    if !crate::routes::users::can_view(&state.pg, &author, &user).await? {
+        return Err(crate::Error::AccountPrivate);
+    }
+
+    let tracks = sqlx::query_as!(
+        Track,
+        "SELECT * FROM tracks WHERE author_id = $1 AND parent_id IS NULL AND track_viewable(tracks, $2) ORDER BY indexed_ts DESC;",
+        author.id,
+        user.as_ref().map(|u| u.id.clone())
+    )
+    .fetch_all(&state.pg)
+    .await?;
 
-    Ok(Json(
-        futures::future::join_all(
-            sqlx::query_as!(
-                Track,
-                "SELECT * FROM tracks WHERE author_id = $1 AND parent_id IS NULL ORDER BY indexed_ts DESC;",
-                other_user
-            )
-            .fetch_all(&state.pg)
-            .await?
-            .into_iter()
-            .map(|post| get_thread(&state.pg, post, false, &user)),
-        )
-        .await
-        .into_iter()
-        .collect::<Result<Vec<Thread>, crate::Error>>()?,
-    ))
+    Ok(Json(get_threads(&state.pg, tracks, &user).await?))
 }
@@ -0,0 +1,60 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::{Thread, Track};
+use serde::Deserialize;
+use sqlx::types::chrono;
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Query},
+    utils::get_threads,
+};
+
+#[derive(Deserialize)]
+pub struct HomeOptions {
+    #[serde(default)]
+    before_ts: Option<i64>,
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+/// Root tracks and reposts by the actors the user follows and their own,
+/// leaving out anyone they've muted, newest first. Pass the `indexed_ts` of the last track as `before_ts` to
+/// get the next page.
+pub async fn route(
+    map: HeaderMap,
+    Query(options): Query<HomeOptions>,
+    State(state): State<crate::GSt>,
+) -> Result<Json<Vec<Thread>>, crate::Error> {
+    let (user, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    let ts = chrono::Utc::now().timestamp_millis();
+    let limit = options.limit.unwrap_or(30).clamp(1, 100);
+
+    let tracks = sqlx::query_as!(
+        Track,
+        "SELECT * FROM tracks WHERE indexed_ts < $1 AND parent_id IS NULL AND (author_id = $2 OR author_id IN (SELECT followee_id FROM follows WHERE follower_id = $2)) AND NOT EXISTS (SELECT 1 FROM mutes WHERE muter_id = $2 AND muted_id = tracks.author_id) AND track_viewable(tracks, $2) ORDER BY indexed_ts DESC LIMIT $3;",
+        options.before_ts.unwrap_or(ts),
+        &user.id,
+        limit
+    )
+    .fetch_all(&state.pg)
+    .await?;
+
+    Ok(Json(get_threads(&state.pg, tracks, &Some(user)).await?))
+}
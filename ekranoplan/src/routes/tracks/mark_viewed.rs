@@ -14,13 +14,10 @@
    limitations under the License.
 */
 
-use axum::{
-    extract::{Path, State},
-    http::HeaderMap,
-};
+use axum::{extract::State, http::HeaderMap};
 use sqlx::types::chrono;
 
-use crate::auth::get_user;
+use crate::{auth::get_user, extract::Path};
 
 pub async fn route(
     map: HeaderMap,
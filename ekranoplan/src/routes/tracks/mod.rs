@@ -15,17 +15,199 @@
 */
 
 use axum::routing::{get, post};
+use models::signing::{self, SIGNATURE_VERSION};
+use sqlx::{PgConnection, PgPool};
+
+use crate::utils::mentions;
 
 pub mod bookmark;
 pub mod create;
 pub mod delete;
+pub mod edit;
 pub mod get_thread;
 pub mod get_user;
+pub mod home;
 pub mod mark_viewed;
 pub mod react;
+pub mod repost;
+pub mod revisions;
 pub mod scroll;
 pub mod unbookmark;
 pub mod unreact;
+pub mod unrepost;
+pub mod verify;
+
+pub const THREAD_TRACK: i32 = 0;
+pub const REPOST_TRACK: i32 = 1;
+pub const QUOTE_TRACK: i32 = 2;
+
+/// Who can see a track, see `track_visible` in the migrations,
+/// which `track_viewable` adds blocks and locked accounts to. Unlisted
+/// tracks are public but kept out of the scroll, and mentioned actors can
+/// always see the tracks they're mentioned in.
+pub const PUBLIC_VISIBILITY: i32 = 0;
+pub const UNLISTED_VISIBILITY: i32 = 1;
+pub const FOLLOWERS_VISIBILITY: i32 = 2;
+pub const MENTIONED_VISIBILITY: i32 = 3;
+
+/// Who besides the author can reply to a track.
+pub const REPLY_EVERYONE: i32 = 0;
+pub const REPLY_FOLLOWERS: i32 = 1;
+pub const REPLY_MENTIONED: i32 = 2;
+
+pub fn visibility(visibility: &i32) -> Result<(), serde_valid::validation::Error> {
+    match *visibility {
+        PUBLIC_VISIBILITY | UNLISTED_VISIBILITY | FOLLOWERS_VISIBILITY | MENTIONED_VISIBILITY => {
+            Ok(())
+        }
+        _ => Err(serde_valid::validation::Error::Custom(
+            "Unknown visibility".to_string(),
+        )),
+    }
+}
+
+pub fn reply_policy(policy: &i32) -> Result<(), serde_valid::validation::Error> {
+    match *policy {
+        REPLY_EVERYONE | REPLY_FOLLOWERS | REPLY_MENTIONED => Ok(()),
+        _ => Err(serde_valid::validation::Error::Custom(
+            "Unknown reply policy".to_string(),
+        )),
+    }
+}
+
+/// Built in reactions, indexed by their type. Types past these are left for
+/// the ids of custom emoji.
+pub const REACTIONS: &[&str] = &["❤️", "😂", "😮", "😢", "😡", "👍", "👎", "🔥"];
+pub const LIKE_REACTION: i64 = 0;
+
+pub fn valid_reaction(kind: i64) -> bool {
+    // custom emoji can't be made yet, so there are none to check ids against
+    (0..REACTIONS.len() as i64).contains(&kind)
+}
+
+/// Finds the track to repost or quote for `track_id`. Reposts point through
+/// to the track they repost, so nothing ever refers to a repost.
+pub async fn reference_target(pg: &PgPool, track_id: &str) -> Result<String, crate::Error> {
+    let track = sqlx::query!(
+        "SELECT id, type, reference_id FROM tracks WHERE id = $1;",
+        track_id
+    )
+    .fetch_optional(pg)
+    .await?
+    .ok_or(crate::Error::TrackNotExist)?;
+
+    if track.r#type == REPOST_TRACK {
+        track.reference_id.ok_or(crate::Error::TrackNotExist)
+    } else {
+        Ok(track.id)
+    }
+}
+
+/// Fails with [`crate::Error::Blocked`] when `actor_id` and the author of
+/// `track_id` have blocked one another, so they can't reply to, react to
+/// or repost each other's tracks.
+pub async fn check_blocked(
+    pg: &PgPool,
+    track_id: &str,
+    actor_id: &str,
+) -> Result<(), crate::Error> {
+    let blocked = sqlx::query!(
+        "SELECT blocks.since FROM tracks JOIN blocks ON (blocks.blocker_id = tracks.author_id AND blocks.blocked_id = $2) OR (blocks.blocker_id = $2 AND blocks.blocked_id = tracks.author_id) WHERE tracks.id = $1;",
+        track_id,
+        actor_id
+    )
+    .fetch_optional(pg)
+    .await?;
+
+    if blocked.is_some() {
+        Err(crate::Error::Blocked)
+    } else {
+        Ok(())
+    }
+}
+
+/// Fails with [`crate::Error::TrackNotExist`] unless `actor_id` may see
+/// `track_id`, so tracks they can't see can't be acted on either.
+pub async fn check_visible(
+    pg: &PgPool,
+    track_id: &str,
+    actor_id: &str,
+) -> Result<(), crate::Error> {
+    sqlx::query!(
+        "SELECT id FROM tracks WHERE id = $1 AND track_viewable(tracks, $2);",
+        track_id,
+        actor_id
+    )
+    .fetch_optional(pg)
+    .await?
+    .ok_or(crate::Error::TrackNotExist)?;
+    Ok(())
+}
+
+/// Fails with [`crate::Error::ReplyRestricted`] when the author of
+/// `parent_id` doesn't let `actor_id` reply to it.
+pub async fn check_reply(pg: &PgPool, parent_id: &str, actor_id: &str) -> Result<(), crate::Error> {
+    let parent = sqlx::query!(
+        "SELECT author_id, reply_policy FROM tracks WHERE id = $1;",
+        parent_id
+    )
+    .fetch_optional(pg)
+    .await?
+    .ok_or(crate::Error::TrackNotExist)?;
+
+    let allowed = match parent.author_id {
+        Some(ref author_id) if author_id == actor_id => true,
+        Some(ref author_id) if parent.reply_policy == REPLY_FOLLOWERS => {
+            crate::routes::users::follow_exists(pg, actor_id, author_id).await?
+        }
+        _ if parent.reply_policy == REPLY_MENTIONED => sqlx::query!(
+            "SELECT actor_id FROM track_mentions WHERE track_id = $1 AND actor_id = $2;",
+            parent_id,
+            actor_id
+        )
+        .fetch_optional(pg)
+        .await?
+        .is_some(),
+        _ => true,
+    };
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(crate::Error::ReplyRestricted)
+    }
+}
+
+/// Replaces the actors `track_id` mentions with the ones in `content`.
+pub async fn save_mentions(
+    conn: &mut PgConnection,
+    track_id: &str,
+    content: &str,
+) -> Result<(), crate::Error> {
+    sqlx::query!("DELETE FROM track_mentions WHERE track_id = $1;", track_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!(
+        "INSERT INTO track_mentions (track_id, actor_id) SELECT $1, id FROM actors WHERE id = ANY($2);",
+        track_id,
+        &mentions(content)
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// What a new track or edit gets signed over, see [`models::signing`].
+pub fn signing_payload(
+    id: &str,
+    author_id: &str,
+    ts: i64,
+    reference_id: Option<&str>,
+    content: &str,
+) -> String {
+    signing::payload(SIGNATURE_VERSION, id, author_id, ts, reference_id, content)
+        .expect("current signature version has a payload")
+}
 
 pub fn router() -> axum::Router<crate::GSt> {
     axum::Router::new()
@@ -33,8 +215,12 @@ pub fn router() -> axum::Router<crate::GSt> {
         .route("/tracks", post(create::route))
         .route(
             "/tracks/:track_id",
-            get(get_thread::route).delete(delete::route),
+            get(get_thread::route)
+                .patch(edit::route)
+                .delete(delete::route),
         )
+        .route("/tracks/:track_id/revisions", get(revisions::route))
+        .route("/tracks/:track_id/verify", get(verify::route))
         .route("/tracks/:track_id/mark", post(mark_viewed::route))
         .route(
             "/tracks/:track_id/bookmark",
@@ -44,5 +230,14 @@ pub fn router() -> axum::Router<crate::GSt> {
             "/tracks/:track_id/react",
             post(react::route).delete(unreact::route),
         )
+        .route(
+            "/tracks/:track_id/react/:type",
+            post(react::route_typed).delete(unreact::route_typed),
+        )
+        .route(
+            "/tracks/:track_id/repost",
+            post(repost::route).delete(unrepost::route),
+        )
         .route("/tracks/scroll", get(scroll::route))
+        .route("/tracks/home", get(home::route))
 }
@@ -14,29 +14,51 @@
    limitations under the License.
 */
 
-use axum::{
-    extract::{Path, State},
-    http::HeaderMap,
-};
+use axum::{extract::State, http::HeaderMap};
 
-use crate::auth::get_user;
+use crate::{auth::get_user, counters::bump_reactions, extract::Path};
 
 pub async fn route(
     map: HeaderMap,
     State(state): State<crate::GSt>,
     Path(track_id): Path<String>,
 ) -> Result<String, crate::Error> {
-    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+    react(&map, &state, track_id, super::LIKE_REACTION).await
+}
+
+pub async fn route_typed(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path((track_id, kind)): Path<(String, i64)>,
+) -> Result<String, crate::Error> {
+    react(&map, &state, track_id, kind).await
+}
+
+async fn react(
+    map: &HeaderMap,
+    state: &crate::GSt,
+    track_id: String,
+    kind: i64,
+) -> Result<String, crate::Error> {
+    let (actor, _) = get_user(map, &state.key, &state.pg).await?;
+
+    if !super::valid_reaction(kind) {
+        return Err(crate::Error::InvalidReaction);
+    }
 
     let post = sqlx::query!("SELECT id FROM tracks WHERE id = $1", track_id)
         .fetch_optional(&state.pg)
         .await?;
 
     if let Some(post) = post {
+        super::check_visible(&state.pg, &post.id, &actor.id).await?;
+        super::check_blocked(&state.pg, &post.id, &actor.id).await?;
+
         let existing_reaction = sqlx::query!(
-            "SELECT user_id FROM track_reactions WHERE track_id = $1 AND user_id = $2;",
+            "SELECT user_id FROM track_reactions WHERE track_id = $1 AND user_id = $2 AND type = $3;",
             post.id,
             actor.id,
+            kind
         )
         .fetch_optional(&state.pg)
         .await?;
@@ -44,13 +66,17 @@ pub async fn route(
         if existing_reaction.is_some() {
             return Err(crate::Error::ReactionExists);
         } else {
+            let mut tx = state.pg.begin().await?;
             sqlx::query!(
-                "INSERT INTO track_reactions (track_id, user_id) VALUES ($1, $2)",
+                "INSERT INTO track_reactions (track_id, user_id, type) VALUES ($1, $2, $3)",
                 post.id,
                 actor.id,
+                kind
             )
-            .execute(&state.pg)
+            .execute(&mut *tx)
             .await?;
+            bump_reactions(&mut tx, &post.id, kind, 1).await?;
+            tx.commit().await?;
         }
 
         Ok("".to_string())
@@ -0,0 +1,72 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::{Track, signing::SIGNATURE_VERSION};
+use sqlx::types::chrono;
+
+use crate::{
+    auth::get_user,
+    counters::count_track,
+    extract::{Json, Path},
+};
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(track_id): Path<String>,
+) -> Result<Json<Track>, crate::Error> {
+    let (actor, account) = get_user(&map, &state.key, &state.pg).await?;
+
+    let reference_id = super::reference_target(&state.pg, &track_id).await?;
+    super::check_visible(&state.pg, &reference_id, &actor.id).await?;
+    super::check_blocked(&state.pg, &reference_id, &actor.id).await?;
+
+    let acc = state.pickles.decrypt(&account.pickle)?;
+
+    let id = state.snow.generate().unwrap().to_string();
+    let ts = chrono::Utc::now().timestamp_millis();
+
+    let sig_fmt = super::signing_payload(&id, &actor.id, ts, Some(&reference_id), "");
+    let sig = acc.sign(sig_fmt).to_base64();
+
+    let mut tx = state.pg.begin().await?;
+
+    let track = sqlx::query_as!(
+        Track,
+        "INSERT INTO tracks (id, type, author_id, content, original_ts, indexed_ts, signature, reference_id, signature_version) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING *;",
+        id,
+        super::REPOST_TRACK,
+        actor.id,
+        "",
+        &ts,
+        &ts,
+        sig,
+        reference_id,
+        SIGNATURE_VERSION
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::Database(ref e) if e.is_unique_violation() => crate::Error::RepostExists,
+        err => err.into(),
+    })?;
+    count_track(&mut tx, &track, 1).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(track))
+}
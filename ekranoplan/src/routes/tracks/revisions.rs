@@ -0,0 +1,57 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::Revision;
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Path},
+};
+
+/// Earlier versions of a track, newest first, for those who may see it.
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(track_id): Path<String>,
+) -> Result<Json<Vec<Revision>>, crate::Error> {
+    let viewer = if map.contains_key("authorization") {
+        let (viewer, _) = get_user(&map, &state.key, &state.pg).await?;
+        Some(viewer.id)
+    } else {
+        None
+    };
+
+    sqlx::query!(
+        "SELECT id FROM tracks WHERE id = $1 AND track_viewable(tracks, $2);",
+        track_id,
+        viewer
+    )
+    .fetch_optional(&state.pg)
+    .await?
+    .ok_or(crate::Error::TrackNotExist)?;
+
+    Ok(Json(
+        sqlx::query_as!(
+            Revision,
+            "SELECT track_revisions.* FROM track_revisions JOIN tracks ON tracks.id = track_revisions.track_id WHERE track_revisions.track_id = $1 AND track_viewable(tracks, $2) ORDER BY ts DESC;",
+            track_id,
+            viewer
+        )
+        .fetch_all(&state.pg)
+        .await?,
+    ))
+}
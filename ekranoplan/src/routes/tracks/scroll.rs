@@ -14,16 +14,16 @@
    limitations under the License.
 */
 
-use axum::{
-    Json,
-    extract::{Query, State},
-    http::HeaderMap,
-};
+use axum::{extract::State, http::HeaderMap};
 use models::{Thread, Track};
 use serde::Deserialize;
 use sqlx::types::chrono;
 
-use crate::{auth::get_user, utils::get_thread};
+use crate::{
+    auth::get_user,
+    extract::{Json, Query},
+    utils::get_threads,
+};
 
 #[derive(Deserialize)]
 pub struct ScrollOptions {
@@ -44,20 +44,15 @@ pub async fn route(
     };
 
     let ts = chrono::Utc::now().timestamp_millis();
-    Ok(Json(
-        futures::future::join_all(
-            sqlx::query_as!(
-                Track,
-                "SELECT * FROM tracks WHERE indexed_ts < $1 AND parent_id IS NULL ORDER BY indexed_ts DESC LIMIT 30;",
-                &options.before_ts.unwrap_or(ts)
-            )
-            .fetch_all(&state.pg)
-            .await?
-            .into_iter()
-            .map(|post| get_thread(&state.pg, post, false, &user)),
-        )
-        .await
-        .into_iter()
-        .collect::<Result<Vec<Thread>, crate::Error>>()?,
-    ))
+    let tracks = sqlx::query_as!(
+        Track,
+        "SELECT * FROM tracks WHERE indexed_ts < $1 AND parent_id IS NULL AND NOT EXISTS (SELECT 1 FROM mutes WHERE muter_id = $2 AND muted_id = tracks.author_id) AND tracks.visibility != $3 AND track_viewable(tracks, $2) ORDER BY indexed_ts DESC LIMIT 30;",
+        &options.before_ts.unwrap_or(ts),
+        user.as_ref().map(|u| u.id.clone()),
+        super::UNLISTED_VISIBILITY
+    )
+    .fetch_all(&state.pg)
+    .await?;
+
+    Ok(Json(get_threads(&state.pg, tracks, &user).await?))
 }
@@ -14,12 +14,13 @@
    limitations under the License.
 */
 
-use axum::{
-    extract::{Path, State},
-    http::HeaderMap,
-};
+use axum::{extract::State, http::HeaderMap};
 
-use crate::auth::get_user;
+use crate::{
+    auth::get_user,
+    counters::{TrackDelta, bump_track},
+    extract::Path,
+};
 
 pub async fn route(
     map: HeaderMap,
@@ -33,13 +34,25 @@ pub async fn route(
         .await?;
 
     if let Some(post) = post {
-        sqlx::query!(
+        let mut tx = state.pg.begin().await?;
+        let removed = sqlx::query!(
             "DELETE FROM track_bookmarks WHERE track_id = $1 AND user_id = $2;",
             post.id,
             actor.id
         )
-        .execute(&state.pg)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        bump_track(
+            &mut tx,
+            &post.id,
+            TrackDelta {
+                bookmarks: -(removed as i64),
+                ..Default::default()
+            },
+        )
         .await?;
+        tx.commit().await?;
         Ok("".to_string())
     } else {
         Err(crate::Error::TrackNotExist)
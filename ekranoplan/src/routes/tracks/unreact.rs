@@ -14,44 +14,54 @@
    limitations under the License.
 */
 
-use axum::{
-    extract::{Path, State},
-    http::HeaderMap,
-};
+use axum::{extract::State, http::HeaderMap};
 
-use crate::auth::get_user;
+use crate::{auth::get_user, counters::bump_reactions, extract::Path};
 
 pub async fn route(
     map: HeaderMap,
     State(state): State<crate::GSt>,
     Path(track_id): Path<String>,
 ) -> Result<String, crate::Error> {
-    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+    unreact(&map, &state, track_id, super::LIKE_REACTION).await
+}
+
+pub async fn route_typed(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path((track_id, kind)): Path<(String, i64)>,
+) -> Result<String, crate::Error> {
+    unreact(&map, &state, track_id, kind).await
+}
+
+async fn unreact(
+    map: &HeaderMap,
+    state: &crate::GSt,
+    track_id: String,
+    kind: i64,
+) -> Result<String, crate::Error> {
+    let (actor, _) = get_user(map, &state.key, &state.pg).await?;
 
     let post = sqlx::query!("SELECT id FROM tracks WHERE id = $1", track_id)
         .fetch_optional(&state.pg)
         .await?;
 
     if let Some(post) = post {
-        let existing_reaction = sqlx::query!(
-            "SELECT user_id FROM track_reactions WHERE track_id = $1 AND user_id = $2;",
+        let mut tx = state.pg.begin().await?;
+        let removed = sqlx::query!(
+            "DELETE FROM track_reactions WHERE track_id = $1 AND user_id = $2 AND type = $3;",
             post.id,
-            actor.id
+            actor.id,
+            kind
         )
-        .fetch_optional(&state.pg)
-        .await?;
-
-        if existing_reaction.is_some() {
-            sqlx::query!(
-                "DELETE FROM track_reactions WHERE track_id = $1 AND user_id = $2;",
-                post.id,
-                actor.id,
-            )
-            .execute(&state.pg)
-            .await?;
-        } else {
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if removed == 0 {
             return Err(crate::Error::ReactionNotExist);
         }
+        bump_reactions(&mut tx, &post.id, kind, -1).await?;
+        tx.commit().await?;
 
         Ok("".to_string())
     } else {
@@ -0,0 +1,70 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::Track;
+
+use crate::{auth::get_user, counters::count_track, extract::Path};
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(track_id): Path<String>,
+) -> Result<String, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    let reference_id = super::reference_target(&state.pg, &track_id).await?;
+
+    let repost = sqlx::query_as!(
+        Track,
+        "SELECT * FROM tracks WHERE reference_id = $1 AND author_id = $2 AND type = $3;",
+        reference_id,
+        actor.id,
+        super::REPOST_TRACK
+    )
+    .fetch_optional(&state.pg)
+    .await?
+    .ok_or(crate::Error::RepostNotExist)?;
+
+    let mut tx = state.pg.begin().await?;
+
+    // reactions and bookmarks don't cascade
+    sqlx::query!(
+        "DELETE FROM track_reactions WHERE track_id = $1;",
+        repost.id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM track_bookmarks WHERE track_id = $1;",
+        repost.id
+    )
+    .execute(&mut *tx)
+    .await?;
+    let removed = sqlx::query!("DELETE FROM tracks WHERE id = $1;", repost.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    // already gone if another request got here first
+    if removed == 0 {
+        return Err(crate::Error::RepostNotExist);
+    }
+    count_track(&mut tx, &repost, -1).await?;
+
+    tx.commit().await?;
+
+    Ok("".to_string())
+}
@@ -0,0 +1,65 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::{Track, Verification, signing::verify_track};
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Path},
+};
+
+/// Checks a track's current signature against its author's public key.
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(track_id): Path<String>,
+) -> Result<Json<Verification>, crate::Error> {
+    let viewer = if map.contains_key("authorization") {
+        let (viewer, _) = get_user(&map, &state.key, &state.pg).await?;
+        Some(viewer.id)
+    } else {
+        None
+    };
+
+    let track = sqlx::query_as!(
+        Track,
+        "SELECT * FROM tracks WHERE id = $1 AND track_viewable(tracks, $2);",
+        track_id,
+        viewer
+    )
+    .fetch_optional(&state.pg)
+    .await?
+    .ok_or(crate::Error::TrackNotExist)?;
+
+    let public_key = if let Some(ref author_id) = track.author_id {
+        sqlx::query!("SELECT public_key FROM actors WHERE id = $1;", author_id)
+            .fetch_optional(&state.pg)
+            .await?
+            .map(|a| a.public_key)
+    } else {
+        None
+    };
+
+    Ok(Json(Verification {
+        valid: public_key
+            .as_deref()
+            .is_some_and(|key| verify_track(&track, key)),
+        track_id: track.id,
+        public_key,
+        signature_version: track.signature_version,
+    }))
+}
@@ -0,0 +1,78 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::Actor;
+
+use crate::{
+    auth::get_user,
+    extract::Path,
+    utils::{get_profile, send_event},
+};
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(other_user): Path<String>,
+) -> Result<String, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+    let other_user = super::find_actor(&state, &map, &other_user)
+        .await?
+        .ok_or(crate::Error::UserNotFound)?
+        .id;
+
+    accept(&state, &actor, &other_user).await?;
+
+    Ok("".to_string())
+}
+
+/// Turns the follow `requester_id` asked `target` for into a follow.
+pub async fn accept(
+    state: &crate::GSt,
+    target: &Actor,
+    requester_id: &str,
+) -> Result<(), crate::Error> {
+    let mut tx = state.pg.begin().await?;
+
+    let removed = sqlx::query!(
+        "DELETE FROM follow_requests WHERE requester_id = $1 AND target_id = $2;",
+        requester_id,
+        &target.id
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if removed == 0 {
+        return Err(crate::Error::FollowRequestNotExist);
+    }
+
+    super::follow::create_follow(state, tx, requester_id, &target.id).await?;
+
+    // the follow is committed by now, so a failure here is only logged
+    match get_profile(&state.pg, target.clone()).await {
+        Ok(profile) => {
+            send_event(
+                &state.consumants,
+                vec![requester_id],
+                crate::X15Message::FollowRequestAccept { profile },
+            )
+            .await
+        }
+        Err(err) => eprintln!("failed to load follow request event: {err:?}"),
+    }
+
+    Ok(())
+}
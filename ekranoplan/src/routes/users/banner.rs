@@ -15,27 +15,19 @@
 */
 
 use axum::{
-    extract::{Path, State},
+    extract::State,
     http::{HeaderMap, HeaderValue},
     response::IntoResponse,
 };
-use models::Actor;
 
-use crate::auth::get_user;
+use crate::extract::Path;
 
 pub async fn route(
     map: HeaderMap,
     State(state): State<crate::GSt>,
     Path(other_user): Path<String>,
 ) -> Result<impl IntoResponse, crate::Error> {
-    let user = if other_user == "@me" {
-        let (user, _) = get_user(&map, &state.key, &state.pg).await?;
-        Some(user)
-    } else {
-        sqlx::query_as!(Actor, "SELECT * FROM actors WHERE id = $1;", other_user)
-            .fetch_optional(&state.pg)
-            .await?
-    };
+    let user = super::find_actor(&state, &map, &other_user).await?;
 
     if let Some(user) = user {
         if let Some(banner) = user.banner {
@@ -0,0 +1,100 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use sqlx::types::chrono;
+
+use crate::{
+    auth::get_user,
+    counters::{ActorDelta, bump_actor},
+    extract::Path,
+};
+
+/// Blocks the user, which also severs any follow or follow request
+/// between the two of you in either direction.
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(other_user): Path<String>,
+) -> Result<String, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+    let other_user = super::find_actor(&state, &map, &other_user)
+        .await?
+        .ok_or(crate::Error::UserNotFound)?
+        .id;
+
+    if actor.id == other_user {
+        return Err(crate::Error::UserBlocked);
+    }
+
+    let mut tx = state.pg.begin().await?;
+
+    sqlx::query!(
+        "INSERT INTO blocks (blocker_id, blocked_id, since) VALUES ($1, $2, $3);",
+        &actor.id,
+        &other_user,
+        chrono::Utc::now().timestamp_millis()
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::Database(ref e) if e.is_unique_violation() => crate::Error::UserBlocked,
+        err => err.into(),
+    })?;
+
+    sqlx::query!(
+        "DELETE FROM follow_requests WHERE (requester_id = $1 AND target_id = $2) OR (requester_id = $2 AND target_id = $1);",
+        &actor.id,
+        &other_user
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    for (follower, followee) in [(&actor.id, &other_user), (&other_user, &actor.id)] {
+        let severed = sqlx::query!(
+            "DELETE FROM follows WHERE follower_id = $1 AND followee_id = $2;",
+            follower,
+            followee
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if severed > 0 {
+            bump_actor(
+                &mut tx,
+                follower,
+                ActorDelta {
+                    followed: -1,
+                    ..Default::default()
+                },
+            )
+            .await?;
+            bump_actor(
+                &mut tx,
+                followee,
+                ActorDelta {
+                    followers: -1,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok("".to_string())
+}
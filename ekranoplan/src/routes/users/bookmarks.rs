@@ -14,33 +14,36 @@
    limitations under the License.
 */
 
-use axum::{
-    Json,
-    extract::{Path, State},
-    http::HeaderMap,
-};
-use models::{Actor, Bookmark};
+use axum::{extract::State, http::HeaderMap};
+use models::Bookmark;
 
-use crate::auth::get_user;
+use crate::{
+    auth::get_user,
+    extract::{Json, Path},
+};
 
 pub async fn route(
     map: HeaderMap,
     State(state): State<crate::GSt>,
     Path(other_user): Path<String>,
 ) -> Result<Json<Vec<Bookmark>>, crate::Error> {
-    let user = if other_user == "@me" {
-        let (user, _) = get_user(&map, &state.key, &state.pg).await?;
-        Some(user)
+    let user = super::find_actor(&state, &map, &other_user).await?;
+    let viewer = if map.contains_key("authorization") {
+        let (viewer, _) = get_user(&map, &state.key, &state.pg).await?;
+        Some(viewer.id)
     } else {
-        sqlx::query_as!(Actor, "SELECT * FROM actors WHERE id = $1;", other_user)
-            .fetch_optional(&state.pg)
-            .await?
+        None
     };
 
     if let Some(user) = user {
-        let bookmarks = sqlx::query!("SELECT * FROM track_bookmarks WHERE user_id = $1", &user.id)
-            .fetch_all(&state.pg)
-            .await?
+        // only the tracks the viewer could see themselves
+        let bookmarks = sqlx::query!(
+            "SELECT track_bookmarks.* FROM track_bookmarks JOIN tracks ON tracks.id = track_bookmarks.track_id WHERE user_id = $1 AND track_viewable(tracks, $2);",
+            &user.id,
+            viewer
+        )
+        .fetch_all(&state.pg)
+        .await?
             .into_iter()
             .map(|b| Bookmark {
                 track_id: b.track_id,
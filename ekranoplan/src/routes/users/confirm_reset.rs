@@ -0,0 +1,81 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use argon2::{
+    Argon2, PasswordHasher,
+    password_hash::{SaltString, rand_core::OsRng},
+};
+use axum::extract::State;
+use serde::Deserialize;
+use serde_valid::Validate;
+
+use crate::{
+    email::{RESET_PASSWORD, consume},
+    utils::close_streams,
+    validation::{self, ValidJson},
+};
+
+#[derive(Deserialize, Validate)]
+pub struct ConfirmReset {
+    pub token: String,
+    #[validate(min_length = 8)]
+    #[validate(max_length = 128)]
+    #[validate(custom = validation::password)]
+    pub password: String,
+}
+
+pub async fn route(
+    State(state): State<crate::GSt>,
+    ValidJson(model): ValidJson<ConfirmReset>,
+) -> Result<String, crate::Error> {
+    let mut tx = state.pg.begin().await?;
+
+    let claims = consume(&mut tx, &model.token, RESET_PASSWORD, &state.key).await?;
+
+    let salt = SaltString::generate(&mut OsRng);
+    let password = Argon2::default()
+        .hash_password(model.password.as_bytes(), &salt)
+        .map_err(|_| crate::Error::FailedPasswordHash)?
+        .to_string();
+
+    // receiving the mail proves the address, so it counts as verified too
+    sqlx::query!(
+        "UPDATE accounts SET password = $1, email_verified = true WHERE id = $2 AND email = $3 RETURNING id;",
+        password,
+        claims.sub,
+        claims.email
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::BadToken)?;
+
+    // whoever knew the old password shouldn't stay logged in
+    let revoked: Vec<String> = sqlx::query!(
+        "DELETE FROM sessions WHERE user_id = $1 RETURNING id;",
+        claims.sub
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|s| s.id)
+    .collect();
+
+    tx.commit().await?;
+
+    close_streams(&state.consumants, &claims.sub, &revoked).await;
+
+    Ok("".to_string())
+}
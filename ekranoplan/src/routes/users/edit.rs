@@ -15,37 +15,58 @@
 */
 
 use argon2::{
-    Argon2, PasswordHasher, PasswordVerifier,
+    Argon2, PasswordHasher,
     password_hash::{SaltString, rand_core::OsRng},
 };
-use axum::{Json, extract::State, http::HeaderMap};
+use axum::{extract::State, http::HeaderMap};
 use models::UserProfile;
 use serde::Deserialize;
+use serde_valid::Validate;
 
-use crate::{auth::get_user, utils::get_profile};
+use crate::{
+    auth::{get_session, verify_password},
+    email::{VERIFY_EMAIL, issue, verification_mail},
+    extract::Json,
+    mailer::send_later,
+    utils::{close_streams, get_profile},
+    validation::{self, ValidJson},
+};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct EditSelf {
     #[serde(default)]
+    #[validate(max_length = 254)]
+    #[validate(custom = |v| validation::optional(v, validation::email))]
     email: Option<String>,
     #[serde(default)]
+    #[validate(min_length = 8)]
+    #[validate(max_length = 128)]
+    #[validate(custom = |v| validation::optional(v, validation::password))]
     new_password: Option<String>,
     #[serde(default)]
     old_password: Option<String>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "validation::nullable")]
+    #[validate(min_length = 1)]
+    #[validate(max_length = 32)]
     display_name: Option<Option<String>>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "validation::nullable")]
+    #[validate(max_length = 300)]
     bio: Option<Option<String>>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "validation::nullable")]
+    #[validate(max_length = 100)]
     status: Option<Option<String>>,
+    /// Whether follows have to be approved first. Unlocking approves every
+    /// pending request.
+    #[serde(default)]
+    locked: Option<bool>,
 }
 
 pub async fn route(
     map: HeaderMap,
     State(state): State<crate::GSt>,
-    Json(model): Json<EditSelf>,
+    ValidJson(model): ValidJson<EditSelf>,
 ) -> Result<Json<UserProfile>, crate::Error> {
-    let (mut actor, account) = get_user(&map, &state.key, &state.pg).await?;
+    let (session_id, mut actor, account) = get_session(&map, &state.key, &state.pg).await?;
 
     let mut tx = state.pg.begin().await?;
 
@@ -76,18 +97,37 @@ pub async fn route(
         actor.status = status;
     }
 
+    let mut pending = Vec::new();
+    if let Some(locked) = model.locked {
+        sqlx::query!(
+            "UPDATE actors SET locked = $1 WHERE id = $2",
+            locked,
+            &actor.id
+        )
+        .execute(&mut *tx)
+        .await?;
+        if actor.locked && !locked {
+            pending = sqlx::query!(
+                "SELECT requester_id FROM follow_requests WHERE target_id = $1 ORDER BY since;",
+                &actor.id
+            )
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .map(|r| r.requester_id)
+            .collect();
+        }
+        actor.locked = locked;
+    }
+
     let mut valid_password = false;
+    let mut revoked = Vec::new();
+    let mut verification = None;
 
     // password-dependant
     let argon = Argon2::default();
     if let Some(old_password) = model.old_password {
-        if argon
-            .verify_password(
-                old_password.as_bytes(),
-                &argon2::PasswordHash::new(&account.password).unwrap(),
-            )
-            .is_err()
-        {
+        if !verify_password(&account, &old_password)? {
             return Err(crate::Error::InvalidFormerPassword);
         } else {
             valid_password = true;
@@ -110,6 +150,18 @@ pub async fn route(
         )
         .execute(&mut *tx)
         .await?;
+
+        // anyone else holding a session may not know the new password
+        revoked = sqlx::query!(
+            "DELETE FROM sessions WHERE user_id = $1 AND id != $2 RETURNING id;",
+            &actor.id,
+            session_id
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|s| s.id)
+        .collect();
     }
     if let Some(email) = model.email {
         if !valid_password {
@@ -117,15 +169,35 @@ pub async fn route(
         }
 
         sqlx::query!(
-            "UPDATE accounts SET email = $1 WHERE id = $2",
+            "UPDATE accounts SET email = $1, email_verified = false WHERE id = $2",
             email,
             &actor.id
         )
         .execute(&mut *tx)
-        .await?;
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(ref e) if e.is_unique_violation() => crate::Error::EmailTaken,
+            err => err.into(),
+        })?;
+        let token = issue(&mut tx, &actor.id, &email, VERIFY_EMAIL, &state.key).await?;
+        verification = Some(verification_mail(&email, &token));
     }
 
     tx.commit().await?;
 
+    if let Some(mail) = verification {
+        send_later(&state.mailer, mail);
+    }
+
+    close_streams(&state.consumants, &actor.id, &revoked).await;
+
+    for requester_id in pending {
+        match super::approve_request::accept(&state, &actor, &requester_id).await {
+            // withdrawn in the meantime
+            Err(crate::Error::FollowRequestNotExist) => {}
+            result => result?,
+        }
+    }
+
     Ok(Json(get_profile(&state.pg, actor).await?))
 }
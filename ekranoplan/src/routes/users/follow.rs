@@ -14,87 +14,230 @@
    limitations under the License.
 */
 
-use axum::{
-    extract::{Path, State},
-    http::HeaderMap,
-};
-use sqlx::types::chrono;
-
 use crate::{
     auth::get_user,
-    utils::{get_channel, send_event},
+    counters::{ActorDelta, bump_actor},
+    extract::Path,
+    snow::SnowflakeGenerator,
+    utils::{create_message, get_channel, get_follows, send_event},
 };
+use axum::{extract::State, http::HeaderMap};
 use models::{Message, Room};
+use sqlx::{PgConnection, Postgres, Transaction, types::chrono};
 
 static MESSAGE_CONTENT: &str = "Hey, I just followed you back. That means we're friends now!";
 
+/// Follows the user, or asks to when their account is locked.
 pub async fn route(
     map: HeaderMap,
     State(state): State<crate::GSt>,
     Path(other_user): Path<String>,
 ) -> Result<String, crate::Error> {
     let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+    let other_user = super::find_actor(&state, &map, &other_user)
+        .await?
+        .ok_or(crate::Error::UserNotFound)?;
 
-    if super::follow_exists(&state.pg, &actor.id, &other_user).await? || actor.id == other_user {
+    if super::follow_exists(&state.pg, &actor.id, &other_user.id).await?
+        || actor.id == other_user.id
+    {
         return Err(crate::Error::UserFollowed);
     }
+    if super::block_exists(&state.pg, &actor.id, &other_user.id).await? {
+        return Err(crate::Error::Blocked);
+    }
+
+    if other_user.locked {
+        let since = chrono::Utc::now().timestamp_millis();
+        sqlx::query!(
+            "INSERT INTO follow_requests (requester_id, target_id, since) VALUES ($1, $2, $3);",
+            &actor.id,
+            &other_user.id,
+            since
+        )
+        .execute(&state.pg)
+        .await
+        .map_err(|err| match err {
+            sqlx::Error::Database(ref e) if e.is_unique_violation() => {
+                crate::Error::FollowRequested
+            }
+            err => err.into(),
+        })?;
+
+        // the request is stored by now, so a failure here is only logged
+        match get_follows(
+            &state.pg,
+            vec![(actor.id, since)],
+            &Some(other_user.clone()),
+        )
+        .await
+        {
+            Ok(mut requests) => {
+                if let Some(request) = requests.pop() {
+                    send_event(
+                        &state.consumants,
+                        vec![&other_user.id],
+                        crate::X15Message::FollowRequestCreate { request },
+                    )
+                    .await;
+                }
+            }
+            Err(err) => eprintln!("failed to load follow request event: {err:?}"),
+        }
+    } else {
+        let tx = state.pg.begin().await?;
+        create_follow(&state, tx, &actor.id, &other_user.id).await?;
+    }
 
-    let mut tx = state.pg.begin().await?;
+    Ok("".to_string())
+}
 
+/// Makes `follower` follow `followee` and commits `tx`, opening a DM
+/// between them once they follow each other.
+pub async fn create_follow(
+    state: &crate::GSt,
+    mut tx: Transaction<'_, Postgres>,
+    follower: &str,
+    followee: &str,
+) -> Result<(), crate::Error> {
     sqlx::query!(
-        "INSERT INTO follows (follower_id, followee_id) VALUES ($1, $2);",
-        &actor.id,
-        &other_user
+        "INSERT INTO follows (follower_id, followee_id, since) VALUES ($1, $2, $3);",
+        follower,
+        followee,
+        chrono::Utc::now().timestamp_millis()
     )
     .execute(&mut *tx)
     .await?;
+    bump_actor(
+        &mut tx,
+        follower,
+        ActorDelta {
+            followed: 1,
+            ..Default::default()
+        },
+    )
+    .await?;
+    bump_actor(
+        &mut tx,
+        followee,
+        ActorDelta {
+            followers: 1,
+            ..Default::default()
+        },
+    )
+    .await?;
 
-    if super::follow_exists(&state.pg, &other_user, &actor.id).await? {
-        let (first_id, second_id) = if actor.id > other_user {
-            (&actor.id, &other_user)
-        } else {
-            (&other_user, &actor.id)
-        };
-        let room_id =
-            blake3::hash(("".to_string() + first_id.as_str() + second_id.as_str()).as_bytes())
-                .to_string();
-
-        // message info
-        let ts = chrono::Utc::now().timestamp_millis();
-        let message_content_hash = blake3::hash(MESSAGE_CONTENT.as_bytes()).to_string();
-        let raw_msg_id = format!("{}/{}/{}/{}", &room_id, &actor.id, ts, message_content_hash);
-        let message_id = blake3::hash(raw_msg_id.as_bytes()).to_string();
-
-        let room = sqlx::query_as!(
-            Room,
-            "INSERT INTO rooms (id, type, last_message_id) VALUES ($1, 0, $2) RETURNING *",
-            room_id,
-            message_id
+    if super::follow_exists(&state.pg, followee, follower).await? {
+        let (room, msg) = open_dm(&mut tx, &state.snow, follower, followee).await?;
+        let room_id = room.id.clone();
+
+        tx.commit().await?;
+
+        // the follow is committed by now, so a failure here is only logged
+        match get_channel(&state.pg, room, None).await {
+            Ok(channel) => {
+                send_event(
+                    &state.consumants,
+                    vec![follower, followee],
+                    crate::X15Message::RoomCreate {
+                        room: channel.room,
+                        members: channel.members,
+                    },
+                )
+                .await
+            }
+            Err(err) => eprintln!("failed to load room event: {err:?}"),
+        }
+        send_event(
+            &state.consumants,
+            vec![follower, followee],
+            crate::X15Message::MessageCreate { room_id, msg },
         )
-        .fetch_one(&mut *tx)
-        .await?;
-        let msg = sqlx::query_as!(Message, "INSERT INTO messages (id, room_id, author_id, content, timestamp) VALUES ($1, $2, $3, $4, $5) RETURNING *;", message_id, room_id, actor.id, MESSAGE_CONTENT, ts)
-            .fetch_one(&mut *tx)
-            .await?;
+        .await;
+    } else {
+        tx.commit().await?;
+    }
 
-        let channel = get_channel(&state.pg, room, None).await?;
+    Ok(())
+}
 
-        send_event(
-            &state.consumants,
-            vec![&actor.id, &other_user],
-            crate::X15Message::RoomCreate {
-                room: channel.room,
-                members: channel.members,
-            },
+/// Opens the DM between two mutuals, or reopens the one they had if they
+/// were mutuals before, and says `follower` followed back in it.
+async fn open_dm(
+    conn: &mut PgConnection,
+    snow: &SnowflakeGenerator,
+    follower: &str,
+    followee: &str,
+) -> Result<(Room, Message), crate::Error> {
+    let (first_id, second_id) = if follower > followee {
+        (follower, followee)
+    } else {
+        (followee, follower)
+    };
+    let room_id = blake3::hash(("".to_string() + first_id + second_id).as_bytes()).to_string();
+
+    sqlx::query!(
+        "INSERT INTO rooms (id, type) VALUES ($1, $2) ON CONFLICT (id) DO NOTHING;",
+        room_id,
+        crate::routes::rooms::DM_ROOM
+    )
+    .execute(&mut *conn)
+    .await?;
+    for member_id in [follower, followee] {
+        sqlx::query!(
+            "INSERT INTO room_members (room_id, actor_id) VALUES ($1, $2) ON CONFLICT DO NOTHING;",
+            room_id,
+            member_id
         )
+        .execute(&mut *conn)
         .await?;
-        send_event(
-            &state.consumants,
-            vec![&actor.id, &other_user],
-            crate::X15Message::MessageCreate { room_id, msg },
+    }
+    let msg = create_message(
+        &mut *conn,
+        snow,
+        &room_id,
+        follower,
+        MESSAGE_CONTENT,
+        None,
+        None,
+    )
+    .await?;
+    let room = sqlx::query_as!(Room, "SELECT * FROM rooms WHERE id = $1;", room_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok((room, msg))
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn refollowing_reopens_dm(pg: PgPool) -> Result<(), crate::Error> {
+        for id in ["1", "2"] {
+            sqlx::query!("INSERT INTO actors (id, public_key) VALUES ($1, '');", id)
+                .execute(&pg)
+                .await?;
+        }
+        let snow = SnowflakeGenerator::default();
+        let mut conn = pg.acquire().await?;
+
+        let (room, _) = open_dm(&mut conn, &snow, "1", "2").await?;
+        // they unfollowed each other, and now "2" follows back again
+        let (reopened, msg) = open_dm(&mut conn, &snow, "2", "1").await?;
+
+        assert_eq!(room.id, reopened.id);
+        assert_eq!(reopened.last_message_id, Some(msg.id));
+        let members = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM room_members WHERE room_id = $1;",
+            room.id
         )
+        .fetch_one(&pg)
         .await?;
+        assert_eq!(members, Some(2));
+        Ok(())
     }
-
-    Ok("".to_string())
 }
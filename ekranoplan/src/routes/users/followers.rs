@@ -0,0 +1,73 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::Follow;
+use serde::Deserialize;
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Path, Query},
+    utils::get_follows,
+};
+
+#[derive(Deserialize)]
+pub struct FollowersOptions {
+    #[serde(default)]
+    before: Option<i64>,
+    #[serde(default)]
+    before_id: Option<String>,
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+/// Who follows the user, most recent first. Pass the `since` and actor
+/// id of the last entry as `before` and `before_id` to get the next page.
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(other_user): Path<String>,
+    Query(options): Query<FollowersOptions>,
+) -> Result<Json<Vec<Follow>>, crate::Error> {
+    let user = super::find_actor(&state, &map, &other_user)
+        .await?
+        .ok_or(crate::Error::UserNotFound)?;
+    let viewer = if map.contains_key("authorization") {
+        let (viewer, _) = get_user(&map, &state.key, &state.pg).await?;
+        Some(viewer)
+    } else {
+        None
+    };
+
+    let rows = sqlx::query!(
+        "SELECT follower_id AS id, since FROM follows WHERE followee_id = $1 AND (since, follower_id) < ($2, $3) ORDER BY since DESC, follower_id DESC LIMIT $4;",
+        user.id,
+        options.before.unwrap_or(i64::MAX),
+        options.before_id.unwrap_or_default(),
+        options.limit.unwrap_or(50).clamp(1, 100)
+    )
+    .fetch_all(&state.pg)
+    .await?;
+
+    Ok(Json(
+        get_follows(
+            &state.pg,
+            rows.into_iter().map(|r| (r.id, r.since)).collect(),
+            &viewer,
+        )
+        .await?,
+    ))
+}
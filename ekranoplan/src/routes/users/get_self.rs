@@ -14,10 +14,10 @@
    limitations under the License.
 */
 
-use axum::{Json, extract::State, http::HeaderMap};
+use axum::{extract::State, http::HeaderMap};
 use models::UserProfile;
 
-use crate::{auth::get_user, utils::get_profile};
+use crate::{auth::get_user, extract::Json, utils::get_profile};
 
 pub async fn route(
     map: HeaderMap,
@@ -0,0 +1,80 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use std::time::Duration;
+
+use axum::{extract::State, http::HeaderMap};
+use models::UserProfile;
+use serde::Deserialize;
+use sqlx::types::chrono;
+
+use crate::{auth::get_user, extract::Json, utils::get_profile};
+
+/// How long an account has to wait before changing its handle again.
+static HANDLE_COOLDOWN: Duration = Duration::from_days(7);
+
+#[derive(Deserialize)]
+pub struct SetHandle {
+    pub handle: String,
+}
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Json(model): Json<SetHandle>,
+) -> Result<Json<UserProfile>, crate::Error> {
+    let (mut actor, account) = get_user(&map, &state.key, &state.pg).await?;
+
+    let handle = super::check_handle(&model.handle)?;
+    if actor.handle.as_ref() == Some(&handle) {
+        return Ok(Json(get_profile(&state.pg, actor).await?));
+    }
+
+    let now = chrono::Utc::now().timestamp_millis();
+    if account
+        .handle_changed_at
+        .is_some_and(|changed_at| now - changed_at < HANDLE_COOLDOWN.as_millis() as i64)
+    {
+        return Err(crate::Error::HandleCooldown);
+    }
+
+    let mut tx = state.pg.begin().await?;
+
+    sqlx::query!(
+        "UPDATE actors SET handle = $1 WHERE id = $2;",
+        &handle,
+        &actor.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::Database(ref e) if e.is_unique_violation() => crate::Error::HandleTaken,
+        err => err.into(),
+    })?;
+    sqlx::query!(
+        "UPDATE accounts SET handle_changed_at = $1 WHERE id = $2;",
+        now,
+        &actor.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    actor.handle = Some(handle);
+
+    Ok(Json(get_profile(&state.pg, actor).await?))
+}
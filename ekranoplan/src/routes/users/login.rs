@@ -14,16 +14,22 @@
    limitations under the License.
 */
 
-use std::time::Duration;
+use std::net::SocketAddr;
 
-use argon2::{Argon2, PasswordVerifier};
-use axum::{Json, extract::State};
+use axum::{
+    extract::{ConnectInfo, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+};
 use jsonwebtoken::EncodingKey;
-use models::{Account, Actor, TokenResult};
+use models::{Account, Actor, MfaChallenge, TokenResult};
 use serde::Deserialize;
-use sqlx::types::chrono;
 
-use crate::auth::Claims;
+use crate::{
+    auth::{create_session, issue_tokens, verify_password},
+    extract::Json,
+    mfa::Challenge,
+};
 
 #[derive(Deserialize)]
 pub struct Login {
@@ -32,9 +38,11 @@ pub struct Login {
 }
 
 pub async fn route(
+    map: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<crate::GSt>,
     Json(model): Json<Login>,
-) -> Result<Json<models::TokenResult>, crate::Error> {
+) -> Result<Response, crate::Error> {
     let account = sqlx::query_as!(
         Account,
         "SELECT * FROM accounts WHERE email = $1;",
@@ -44,40 +52,33 @@ pub async fn route(
     .await?;
 
     if let Some(account) = account {
-        let argon = Argon2::default();
-        if argon
-            .verify_password(
-                model.password.as_bytes(),
-                &argon2::PasswordHash::new(&account.password).unwrap(),
-            )
-            .is_ok()
-        {
+        if verify_password(&account, &model.password)? {
+            if account.totp_enabled {
+                let challenge = Challenge::new(account.id);
+                challenge.save(&state.pg).await?;
+                return Ok(Json(MfaChallenge {
+                    challenge: challenge
+                        .make_token(&EncodingKey::from_secret(state.key.as_bytes()))?,
+                })
+                .into_response());
+            }
+
             let actor = sqlx::query_as!(Actor, "SELECT * FROM actors WHERE id = $1;", &account.id)
                 .fetch_one(&state.pg)
                 .await?;
-            let session_id = nanoid::nanoid!();
-
-            sqlx::query!(
-                "INSERT INTO sessions (id, user_id) VALUES ($1, $2);",
-                &session_id,
-                &actor.id
-            )
-            .execute(&state.pg)
-            .await?;
+            let mut tx = state.pg.begin().await?;
+            let session_id = create_session(&mut tx, &actor.id, &map, addr).await?;
+            let (token, refresh_token) =
+                issue_tokens(&mut tx, &session_id, &actor.id, &state.key).await?;
+            tx.commit().await?;
 
-            let time = chrono::Utc::now().timestamp_millis() as u128;
             Ok(Json(TokenResult {
                 actor,
                 account,
-                token: Claims {
-                    sub: session_id,
-                    exp: (time + Duration::from_weeks(6).as_millis())
-                        .try_into()
-                        .unwrap(),
-                    iat: time as usize,
-                }
-                .make_token(&EncodingKey::from_secret(state.key.as_bytes()))?,
-            }))
+                token,
+                refresh_token,
+            })
+            .into_response())
         } else {
             Err(crate::Error::Argon2Error)
         }
@@ -0,0 +1,85 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::HeaderMap,
+};
+use jsonwebtoken::DecodingKey;
+use models::{Account, Actor, TokenResult};
+use serde::Deserialize;
+
+use crate::{
+    auth::{create_session, issue_tokens},
+    extract::Json,
+    mfa::{Challenge, check_code, check_lockout, record_failure},
+};
+
+#[derive(Deserialize)]
+pub struct LoginTotp {
+    pub challenge: String,
+    pub code: String,
+}
+
+pub async fn route(
+    map: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<crate::GSt>,
+    Json(model): Json<LoginTotp>,
+) -> Result<Json<TokenResult>, crate::Error> {
+    let challenge = Challenge::from_token(
+        &model.challenge,
+        &DecodingKey::from_secret(state.key.as_bytes()),
+    )?;
+    challenge.check(&state.pg).await?;
+
+    let account = sqlx::query_as!(
+        Account,
+        "SELECT * FROM accounts WHERE id = $1;",
+        challenge.sub
+    )
+    .fetch_one(&state.pg)
+    .await?;
+    let actor = sqlx::query_as!(Actor, "SELECT * FROM actors WHERE id = $1;", &account.id)
+        .fetch_one(&state.pg)
+        .await?;
+
+    check_lockout(&account)?;
+
+    let mut tx = state.pg.begin().await?;
+
+    if let Err(err) = check_code(&mut tx, &account, &model.code).await {
+        if let crate::Error::InvalidTotpCode = err {
+            record_failure(&state.pg, &account.id, Some(&challenge)).await?;
+        }
+        return Err(err);
+    }
+    challenge.consume(&mut tx).await?;
+
+    let session_id = create_session(&mut tx, &actor.id, &map, addr).await?;
+    let (token, refresh_token) = issue_tokens(&mut tx, &session_id, &actor.id, &state.key).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(TokenResult {
+        actor,
+        account,
+        token,
+        refresh_token,
+    }))
+}
@@ -0,0 +1,33 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+
+use crate::{auth::get_session, utils::close_streams};
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+) -> Result<String, crate::Error> {
+    let (session_id, actor, _) = get_session(&map, &state.key, &state.pg).await?;
+
+    sqlx::query!("DELETE FROM sessions WHERE id = $1;", session_id)
+        .execute(&state.pg)
+        .await?;
+    close_streams(&state.consumants, &actor.id, &[session_id]).await;
+
+    Ok("".to_string())
+}
@@ -16,21 +16,53 @@
 
 use axum::{
     extract::DefaultBodyLimit,
-    routing::{get, patch, post},
+    http::HeaderMap,
+    routing::{delete, get, patch, post},
 };
+use lazy_static::lazy_static;
+use models::Actor;
+use regex::Regex;
 use sqlx::PgPool;
 
+use crate::auth::get_user;
+
+pub mod approve_request;
 pub mod avatar;
 pub mod banner;
+pub mod block;
+pub mod blocks;
 pub mod bookmarks;
+pub mod confirm_reset;
 pub mod edit;
 pub mod follow;
+pub mod follow_requests;
+pub mod followers;
+pub mod following;
 pub mod get_self;
+pub mod handle;
 pub mod login;
+pub mod login_totp;
+pub mod logout;
+pub mod mute;
+pub mod mutes;
 pub mod new_assets;
 pub mod profile;
+pub mod refresh;
 pub mod register;
+pub mod reject_request;
+pub mod request_reset;
+pub mod revoke_session;
+pub mod revoke_sessions;
+pub mod send_verification;
+pub mod sent_requests;
+pub mod sessions;
+pub mod totp_confirm;
+pub mod totp_disable;
+pub mod totp_enroll;
+pub mod unblock;
 pub mod unfollow;
+pub mod unmute;
+pub mod verify_email;
 
 pub async fn follow_exists(
     db: &PgPool,
@@ -52,19 +84,159 @@ pub async fn follow_exists(
     }
 }
 
+/// Whether either actor has blocked the other, which rules out any
+/// interaction between them.
+pub async fn block_exists(db: &PgPool, first: &str, second: &str) -> Result<bool, crate::Error> {
+    Ok(sqlx::query!(
+        "SELECT since FROM blocks WHERE (blocker_id = $1 AND blocked_id = $2) OR (blocker_id = $2 AND blocked_id = $1);",
+        first,
+        second
+    )
+    .fetch_optional(db)
+    .await?
+    .is_some())
+}
+
+/// Whether `viewer` may see the tracks of `author`, which for locked
+/// actors is only themselves and their approved followers.
+pub async fn can_view(
+    db: &PgPool,
+    author: &Actor,
+    viewer: &Option<Actor>,
+) -> Result<bool, crate::Error> {
+    if !author.locked {
+        return Ok(true);
+    }
+    match viewer {
+        Some(viewer) if viewer.id == author.id => Ok(true),
+        Some(viewer) => follow_exists(db, &viewer.id, &author.id).await,
+        None => Ok(false),
+    }
+}
+
+lazy_static! {
+    static ref HANDLE_REGEX: Regex = Regex::new(r"^[a-z0-9_]{3,20}$").unwrap();
+}
+
+/// Handles which would be confusing or collide with routes, like `@me`.
+const RESERVED_HANDLES: &[&str] = &[
+    "me",
+    "admin",
+    "administrator",
+    "api",
+    "derailed",
+    "everyone",
+    "help",
+    "here",
+    "login",
+    "logout",
+    "mod",
+    "moderator",
+    "null",
+    "root",
+    "settings",
+    "staff",
+    "support",
+    "system",
+    "undefined",
+];
+
+/// Handles are case-insensitive and may be written with or without their `@`.
+pub fn normalize_handle(handle: &str) -> String {
+    handle.trim().trim_start_matches('@').to_lowercase()
+}
+
+/// Normalizes `handle` and checks it may be claimed.
+pub fn check_handle(handle: &str) -> Result<String, crate::Error> {
+    let handle = normalize_handle(handle);
+    if !HANDLE_REGEX.is_match(&handle) {
+        Err(crate::Error::InvalidHandle)
+    } else if RESERVED_HANDLES.contains(&handle.as_str()) {
+        Err(crate::Error::HandleReserved)
+    } else {
+        Ok(handle)
+    }
+}
+
+/// Looks up the actor a `:user_id` path segment refers to, which is
+/// either `@me`, `@handle` or an actor id.
+pub async fn find_actor(
+    state: &crate::GSt,
+    map: &HeaderMap,
+    other_user: &str,
+) -> Result<Option<Actor>, crate::Error> {
+    if other_user == "@me" {
+        let (user, _) = get_user(map, &state.key, &state.pg).await?;
+        Ok(Some(user))
+    } else if other_user.starts_with('@') {
+        Ok(sqlx::query_as!(
+            Actor,
+            "SELECT * FROM actors WHERE handle = $1;",
+            normalize_handle(other_user)
+        )
+        .fetch_optional(&state.pg)
+        .await?)
+    } else {
+        Ok(
+            sqlx::query_as!(Actor, "SELECT * FROM actors WHERE id = $1;", other_user)
+                .fetch_optional(&state.pg)
+                .await?,
+        )
+    }
+}
+
 pub fn router() -> axum::Router<crate::GSt> {
     axum::Router::new()
         .route("/create", post(register::route))
         .route("/login", post(login::route))
+        .route("/login/totp", post(login_totp::route))
+        .route("/logout", post(logout::route))
+        .route("/refresh", post(refresh::route))
+        .route("/verify-email", post(verify_email::route))
+        .route("/password-reset", post(request_reset::route))
+        .route("/password-reset/confirm", post(confirm_reset::route))
         .route(
             "/users/:user_id/follow",
             post(follow::route).delete(unfollow::route),
         )
+        .route(
+            "/users/:user_id/block",
+            post(block::route).delete(unblock::route),
+        )
+        .route(
+            "/users/:user_id/mute",
+            post(mute::route).delete(unmute::route),
+        )
+        .route("/users/:user_id/followers", get(followers::route))
+        .route("/users/:user_id/following", get(following::route))
         .route("/users/:user_id", get(profile::route))
         .route("/users/:user_id/avatar", get(avatar::route))
         .route("/users/:user_id/banner", get(banner::route))
         .route("/users/:user_id/bookmarks", get(bookmarks::route))
         .route("/users/@me", patch(edit::route).get(get_self::route))
         .route("/users/@me/assets", patch(new_assets::route))
+        .route("/users/@me/handle", patch(handle::route))
+        .route("/users/@me/blocks", get(blocks::route))
+        .route("/users/@me/mutes", get(mutes::route))
+        .route("/users/@me/follow-requests", get(follow_requests::route))
+        .route("/users/@me/follow-requests/sent", get(sent_requests::route))
+        .route(
+            "/users/@me/follow-requests/:user_id",
+            post(approve_request::route).delete(reject_request::route),
+        )
+        .route("/users/@me/email/verify", post(send_verification::route))
+        .route(
+            "/users/@me/totp",
+            post(totp_enroll::route).delete(totp_disable::route),
+        )
+        .route("/users/@me/totp/confirm", post(totp_confirm::route))
+        .route(
+            "/users/@me/sessions",
+            get(sessions::route).delete(revoke_sessions::route),
+        )
+        .route(
+            "/users/@me/sessions/:session_id",
+            delete(revoke_session::route),
+        )
         .layer(DefaultBodyLimit::max(14_680_064))
 }
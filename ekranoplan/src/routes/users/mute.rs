@@ -0,0 +1,53 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use sqlx::types::chrono;
+
+use crate::{auth::get_user, extract::Path};
+
+/// Mutes the user. Muted actors are left out of the scroll, the home
+/// timeline and mention notifications, but are never told about it.
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(other_user): Path<String>,
+) -> Result<String, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+    let other_user = super::find_actor(&state, &map, &other_user)
+        .await?
+        .ok_or(crate::Error::UserNotFound)?
+        .id;
+
+    if actor.id == other_user {
+        return Err(crate::Error::UserMuted);
+    }
+
+    sqlx::query!(
+        "INSERT INTO mutes (muter_id, muted_id, since) VALUES ($1, $2, $3);",
+        &actor.id,
+        &other_user,
+        chrono::Utc::now().timestamp_millis()
+    )
+    .execute(&state.pg)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::Database(ref e) if e.is_unique_violation() => crate::Error::UserMuted,
+        err => err.into(),
+    })?;
+
+    Ok("".to_string())
+}
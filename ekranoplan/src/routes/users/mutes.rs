@@ -0,0 +1,60 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::Follow;
+use serde::Deserialize;
+
+use crate::{
+    auth::get_user,
+    extract::{Json, Query},
+    utils::get_follows,
+};
+
+#[derive(Deserialize)]
+pub struct MutesOptions {
+    #[serde(default)]
+    before: Option<i64>,
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+/// Who the user has muted, most recent first. Paginated like followers.
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Query(options): Query<MutesOptions>,
+) -> Result<Json<Vec<Follow>>, crate::Error> {
+    let (user, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    let rows = sqlx::query!(
+        "SELECT muted_id AS id, since FROM mutes WHERE muter_id = $1 AND since < $2 ORDER BY since DESC LIMIT $3;",
+        user.id,
+        options.before.unwrap_or(i64::MAX),
+        options.limit.unwrap_or(50).clamp(1, 100)
+    )
+    .fetch_all(&state.pg)
+    .await?;
+
+    Ok(Json(
+        get_follows(
+            &state.pg,
+            rows.into_iter().map(|r| (r.id, r.since)).collect(),
+            &Some(user),
+        )
+        .await?,
+    ))
+}
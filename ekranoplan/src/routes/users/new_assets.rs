@@ -15,7 +15,6 @@
 */
 
 use axum::{
-    Json,
     body::Bytes,
     extract::{Multipart, State},
     http::HeaderMap,
@@ -24,7 +23,7 @@ use caesium::parameters::CSParameters;
 use image::EncodableLayout;
 use models::UserProfile;
 
-use crate::{auth::get_user, utils::get_profile};
+use crate::{auth::get_user, extract::Json, utils::get_profile};
 
 pub async fn route(
     map: HeaderMap,
@@ -14,28 +14,20 @@
    limitations under the License.
 */
 
-use axum::{
-    Json,
-    extract::{Path, State},
-    http::HeaderMap,
-};
-use models::{Actor, UserProfile};
+use axum::{extract::State, http::HeaderMap};
+use models::UserProfile;
 
-use crate::{auth::get_user, utils::get_profile};
+use crate::{
+    extract::{Json, Path},
+    utils::get_profile,
+};
 
 pub async fn route(
     map: HeaderMap,
     State(state): State<crate::GSt>,
     Path(other_user): Path<String>,
 ) -> Result<Json<UserProfile>, crate::Error> {
-    let user = if other_user == "@me" {
-        let (user, _) = get_user(&map, &state.key, &state.pg).await?;
-        Some(user)
-    } else {
-        sqlx::query_as!(Actor, "SELECT * FROM actors WHERE id = $1;", other_user)
-            .fetch_optional(&state.pg)
-            .await?
-    };
+    let user = super::find_actor(&state, &map, &other_user).await?;
 
     if let Some(user) = user {
         Ok(Json(get_profile(&state.pg, user).await?))
@@ -0,0 +1,102 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::extract::State;
+use models::{Account, Actor, TokenResult};
+use serde::Deserialize;
+use sqlx::types::chrono;
+
+use crate::{auth::issue_tokens, extract::Json, utils::close_streams};
+
+#[derive(Deserialize)]
+pub struct Refresh {
+    pub refresh_token: String,
+}
+
+pub async fn route(
+    State(state): State<crate::GSt>,
+    Json(model): Json<Refresh>,
+) -> Result<Json<TokenResult>, crate::Error> {
+    let token_hash = blake3::hash(model.refresh_token.as_bytes()).to_string();
+    let time = chrono::Utc::now().timestamp_millis();
+
+    let mut tx = state.pg.begin().await?;
+
+    let token = sqlx::query!(
+        "SELECT * FROM refresh_tokens WHERE token_hash = $1 FOR UPDATE;",
+        token_hash
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::BadToken)?;
+
+    // a token can only be exchanged once, seeing it again means someone
+    // else has a copy so the whole session goes
+    if token.used {
+        sqlx::query!(
+            "DELETE FROM sessions WHERE id = $1 AND user_id = $2;",
+            token.session_id,
+            token.user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        close_streams(&state.consumants, &token.user_id, &[token.session_id]).await;
+        return Err(crate::Error::ExpiredSession);
+    }
+
+    if token.expires_at < time {
+        return Err(crate::Error::ExpiredSession);
+    }
+
+    sqlx::query!(
+        "UPDATE refresh_tokens SET used = true WHERE token_hash = $1;",
+        token_hash
+    )
+    .execute(&mut *tx)
+    .await?;
+    // used tokens only need to be kept around until they would have expired
+    sqlx::query!(
+        "DELETE FROM refresh_tokens WHERE session_id = $1 AND expires_at < $2;",
+        token.session_id,
+        time
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let (access_token, refresh_token) =
+        issue_tokens(&mut tx, &token.session_id, &token.user_id, &state.key).await?;
+
+    let account = sqlx::query_as!(
+        Account,
+        "SELECT * FROM accounts WHERE id = $1;",
+        token.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let actor = sqlx::query_as!(Actor, "SELECT * FROM actors WHERE id = $1;", token.user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(TokenResult {
+        actor,
+        account,
+        token: access_token,
+        refresh_token,
+    }))
+}
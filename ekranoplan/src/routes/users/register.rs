@@ -14,30 +14,44 @@
    limitations under the License.
 */
 
-use std::time::Duration;
+use std::net::SocketAddr;
 
 use argon2::{
     Argon2, PasswordHasher,
     password_hash::{SaltString, rand_core::OsRng},
 };
-use axum::{Json, extract::State};
-use jsonwebtoken::EncodingKey;
+use axum::{
+    extract::{ConnectInfo, State},
+    http::HeaderMap,
+};
 use models::{Account, Actor, TokenResult};
 use serde::Deserialize;
 use serde_valid::Validate;
-use sqlx::types::chrono;
 
-use crate::auth::Claims;
+use crate::{
+    auth::{create_session, issue_tokens},
+    email::{VERIFY_EMAIL, issue, verification_mail},
+    extract::Json,
+    mailer::send_later,
+    validation::{self, ValidJson},
+};
 
 #[derive(Deserialize, Validate)]
 pub struct Register {
+    #[validate(max_length = 254)]
+    #[validate(custom = validation::email)]
     pub email: String,
+    #[validate(min_length = 8)]
+    #[validate(max_length = 128)]
+    #[validate(custom = validation::password)]
     pub password: String,
 }
 
 pub async fn route(
+    map: HeaderMap,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     State(state): State<crate::GSt>,
-    Json(model): Json<Register>,
+    ValidJson(model): ValidJson<Register>,
 ) -> Result<Json<models::TokenResult>, crate::Error> {
     let mut tx = state.pg.begin().await?;
 
@@ -54,7 +68,7 @@ pub async fn route(
 
     let acc = vodozemac::olm::Account::new();
     let public_key = acc.ed25519_key().to_base64();
-    let pickle = acc.pickle().encrypt(&crate::PICKLE_KEY);
+    let pickle = state.pickles.encrypt(&acc);
 
     let actor = sqlx::query_as!(
         Actor,
@@ -64,31 +78,38 @@ pub async fn route(
     )
     .fetch_one(&mut *tx)
     .await?;
-    let account = sqlx::query_as!(Account, "INSERT INTO accounts (id, email, password, admin, theme, pickle) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *;", &user_id, model.email, password_hash, false, "dark", pickle).fetch_one(&mut *tx).await?;
-
-    let session_id = nanoid::nanoid!();
-
-    sqlx::query!(
-        "INSERT INTO sessions (id, user_id) VALUES ($1, $2);",
-        &session_id,
-        &actor.id
+    let account = sqlx::query_as!(
+        Account,
+        "INSERT INTO accounts (id, email, password, admin, theme, pickle) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *;",
+        &user_id,
+        model.email,
+        password_hash,
+        false,
+        "dark",
+        pickle
     )
-    .execute(&mut *tx)
-    .await?;
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::Database(ref e) if e.is_unique_violation() => crate::Error::EmailTaken,
+        err => err.into(),
+    })?;
+
+    let session_id = create_session(&mut tx, &actor.id, &map, addr).await?;
+    let (token, refresh_token) = issue_tokens(&mut tx, &session_id, &actor.id, &state.key).await?;
+    let verify_token = issue(&mut tx, &actor.id, &model.email, VERIFY_EMAIL, &state.key).await?;
 
     tx.commit().await?;
 
-    let time = chrono::Utc::now().timestamp_millis() as u128;
+    send_later(
+        &state.mailer,
+        verification_mail(&model.email, &verify_token),
+    );
+
     Ok(Json(TokenResult {
         actor,
         account,
-        token: Claims {
-            sub: session_id,
-            exp: (time + Duration::from_weeks(6).as_millis())
-                .try_into()
-                .unwrap(),
-            iat: time as usize,
-        }
-        .make_token(&EncodingKey::from_secret(state.key.as_bytes()))?,
+        token,
+        refresh_token,
     }))
 }
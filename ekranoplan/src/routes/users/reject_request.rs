@@ -0,0 +1,45 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+
+use crate::{auth::get_user, extract::Path};
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Path(other_user): Path<String>,
+) -> Result<String, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+    let other_user = super::find_actor(&state, &map, &other_user)
+        .await?
+        .ok_or(crate::Error::UserNotFound)?
+        .id;
+
+    let removed = sqlx::query!(
+        "DELETE FROM follow_requests WHERE requester_id = $1 AND target_id = $2;",
+        &other_user,
+        &actor.id
+    )
+    .execute(&state.pg)
+    .await?
+    .rows_affected();
+    if removed == 0 {
+        return Err(crate::Error::FollowRequestNotExist);
+    }
+
+    Ok("".to_string())
+}
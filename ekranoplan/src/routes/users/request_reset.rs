@@ -0,0 +1,55 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::extract::State;
+use serde::Deserialize;
+
+use crate::{
+    email::{RESET_PASSWORD, issue, reset_mail},
+    extract::Json,
+    mailer::send_later,
+};
+
+#[derive(Deserialize)]
+pub struct RequestReset {
+    pub email: String,
+}
+
+/// Mails a reset link if an account uses `email`. Always answers the same way
+/// so it can't be used to find out which addresses are registered.
+pub async fn route(
+    State(state): State<crate::GSt>,
+    Json(model): Json<RequestReset>,
+) -> Result<String, crate::Error> {
+    let account = sqlx::query!("SELECT id FROM accounts WHERE email = $1;", model.email)
+        .fetch_optional(&state.pg)
+        .await?;
+
+    if let Some(account) = account {
+        let mut conn = state.pg.acquire().await?;
+        let token = issue(
+            &mut conn,
+            &account.id,
+            &model.email,
+            RESET_PASSWORD,
+            &state.key,
+        )
+        .await?;
+        send_later(&state.mailer, reset_mail(&model.email, &token));
+    }
+
+    Ok("".to_string())
+}
@@ -0,0 +1,39 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+
+use crate::{
+    auth::get_session,
+    email::{VERIFY_EMAIL, issue, verification_mail},
+    mailer::send_later,
+};
+
+/// Sends a fresh verification link to the account's current address.
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+) -> Result<String, crate::Error> {
+    let (_, actor, account) = get_session(&map, &state.key, &state.pg).await?;
+
+    let email = account.email.ok_or(crate::Error::NoEmail)?;
+
+    let mut conn = state.pg.acquire().await?;
+    let token = issue(&mut conn, &actor.id, &email, VERIFY_EMAIL, &state.key).await?;
+    send_later(&state.mailer, verification_mail(&email, &token));
+
+    Ok("".to_string())
+}
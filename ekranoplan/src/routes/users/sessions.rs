@@ -0,0 +1,37 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use models::Session;
+
+use crate::{auth::get_user, extract::Json};
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+) -> Result<Json<Vec<Session>>, crate::Error> {
+    let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+
+    Ok(Json(
+        sqlx::query_as!(
+            Session,
+            "SELECT * FROM sessions WHERE user_id = $1 ORDER BY last_seen DESC;",
+            actor.id
+        )
+        .fetch_all(&state.pg)
+        .await?,
+    ))
+}
@@ -0,0 +1,68 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::get_user,
+    extract::Json,
+    mfa::{check_totp, new_recovery_codes},
+};
+
+#[derive(Deserialize)]
+pub struct ConfirmTotp {
+    code: String,
+}
+
+#[derive(Serialize)]
+pub struct RecoveryCodes {
+    recovery_codes: Vec<String>,
+}
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Json(model): Json<ConfirmTotp>,
+) -> Result<Json<RecoveryCodes>, crate::Error> {
+    let (_, account) = get_user(&map, &state.key, &state.pg).await?;
+
+    if account.totp_enabled {
+        return Err(crate::Error::TotpEnabled);
+    }
+    let secret = account
+        .totp_secret
+        .as_ref()
+        .ok_or(crate::Error::TotpNotEnabled)?;
+
+    let mut tx = state.pg.begin().await?;
+
+    if !check_totp(&mut tx, &account.id, secret, &model.code).await? {
+        return Err(crate::Error::InvalidTotpCode);
+    }
+
+    sqlx::query!(
+        "UPDATE accounts SET totp_enabled = true WHERE id = $1;",
+        account.id
+    )
+    .execute(&mut *tx)
+    .await?;
+    let recovery_codes = new_recovery_codes(&mut tx, &account.id).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(RecoveryCodes { recovery_codes }))
+}
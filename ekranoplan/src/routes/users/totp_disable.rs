@@ -0,0 +1,70 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use serde::Deserialize;
+
+use crate::{
+    auth::{get_user, verify_password},
+    extract::Json,
+    mfa::{check_code, check_lockout, record_failure},
+};
+
+#[derive(Deserialize)]
+pub struct DisableTotp {
+    password: String,
+    code: String,
+}
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Json(model): Json<DisableTotp>,
+) -> Result<String, crate::Error> {
+    let (_, account) = get_user(&map, &state.key, &state.pg).await?;
+
+    if !verify_password(&account, &model.password)? {
+        return Err(crate::Error::InvalidFormerPassword);
+    }
+    if !account.totp_enabled {
+        return Err(crate::Error::TotpNotEnabled);
+    }
+
+    check_lockout(&account)?;
+
+    let mut tx = state.pg.begin().await?;
+
+    if let Err(err) = check_code(&mut tx, &account, &model.code).await {
+        if let crate::Error::InvalidTotpCode = err {
+            record_failure(&state.pg, &account.id, None).await?;
+        }
+        return Err(err);
+    }
+
+    sqlx::query!(
+        "UPDATE accounts SET totp_secret = NULL, totp_enabled = false WHERE id = $1;",
+        account.id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!("DELETE FROM recovery_codes WHERE user_id = $1;", account.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok("".to_string())
+}
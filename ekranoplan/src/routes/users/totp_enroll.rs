@@ -0,0 +1,63 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::{extract::State, http::HeaderMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::{get_user, verify_password},
+    extract::Json,
+    mfa::{new_secret, totp},
+};
+
+#[derive(Deserialize)]
+pub struct EnrollTotp {
+    password: String,
+}
+
+#[derive(Serialize)]
+pub struct TotpSetup {
+    secret: String,
+    url: String,
+}
+
+pub async fn route(
+    map: HeaderMap,
+    State(state): State<crate::GSt>,
+    Json(model): Json<EnrollTotp>,
+) -> Result<Json<TotpSetup>, crate::Error> {
+    let (_, account) = get_user(&map, &state.key, &state.pg).await?;
+
+    if !verify_password(&account, &model.password)? {
+        return Err(crate::Error::InvalidFormerPassword);
+    }
+    if account.totp_enabled {
+        return Err(crate::Error::TotpEnabled);
+    }
+
+    // not used for anything until confirmed with a code from it
+    let secret = new_secret();
+    sqlx::query!(
+        "UPDATE accounts SET totp_secret = $1 WHERE id = $2;",
+        secret,
+        account.id
+    )
+    .execute(&state.pg)
+    .await?;
+
+    let url = totp(&secret, account.email.as_ref().unwrap_or(&account.id))?.get_url();
+    Ok(Json(TotpSetup { secret, url }))
+}
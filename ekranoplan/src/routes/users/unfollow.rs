@@ -14,12 +14,13 @@
    limitations under the License.
 */
 
-use axum::{
-    extract::{Path, State},
-    http::HeaderMap,
-};
+use axum::{extract::State, http::HeaderMap};
 
-use crate::auth::get_user;
+use crate::{
+    auth::get_user,
+    counters::{ActorDelta, bump_actor},
+    extract::Path,
+};
 
 pub async fn route(
     map: HeaderMap,
@@ -27,18 +28,61 @@ pub async fn route(
     Path(other_user): Path<String>,
 ) -> Result<String, crate::Error> {
     let (actor, _) = get_user(&map, &state.key, &state.pg).await?;
+    let other_user = super::find_actor(&state, &map, &other_user)
+        .await?
+        .ok_or(crate::Error::UserNotFound)?
+        .id;
 
     if !super::follow_exists(&state.pg, &actor.id, &other_user).await? {
-        return Err(crate::Error::UserNotFollowed);
+        // withdraws a follow request which hasn't been answered yet
+        let withdrawn = sqlx::query!(
+            "DELETE FROM follow_requests WHERE requester_id = $1 AND target_id = $2;",
+            &actor.id,
+            &other_user
+        )
+        .execute(&state.pg)
+        .await?
+        .rows_affected();
+        if withdrawn == 0 {
+            return Err(crate::Error::UserNotFollowed);
+        }
+        return Ok("".to_string());
     }
 
-    sqlx::query!(
+    let mut tx = state.pg.begin().await?;
+
+    let removed = sqlx::query!(
         "DELETE FROM follows WHERE follower_id = $1 AND followee_id = $2;",
         &actor.id,
         &other_user
     )
-    .execute(&state.pg)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    // already gone if another request got here first
+    if removed == 0 {
+        return Err(crate::Error::UserNotFollowed);
+    }
+    bump_actor(
+        &mut tx,
+        &actor.id,
+        ActorDelta {
+            followed: -1,
+            ..Default::default()
+        },
+    )
     .await?;
+    bump_actor(
+        &mut tx,
+        &other_user,
+        ActorDelta {
+            followers: -1,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
 
     Ok("".to_string())
 }
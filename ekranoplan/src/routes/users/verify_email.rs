@@ -0,0 +1,50 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use axum::extract::State;
+use serde::Deserialize;
+
+use crate::email::{VERIFY_EMAIL, consume};
+
+use crate::extract::Json;
+
+#[derive(Deserialize)]
+pub struct VerifyEmail {
+    pub token: String,
+}
+
+pub async fn route(
+    State(state): State<crate::GSt>,
+    Json(model): Json<VerifyEmail>,
+) -> Result<String, crate::Error> {
+    let mut tx = state.pg.begin().await?;
+
+    let claims = consume(&mut tx, &model.token, VERIFY_EMAIL, &state.key).await?;
+
+    // the link only counts for the address it was sent to
+    sqlx::query!(
+        "UPDATE accounts SET email_verified = true WHERE id = $1 AND email = $2 RETURNING id;",
+        claims.sub,
+        claims.email
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(crate::Error::BadToken)?;
+
+    tx.commit().await?;
+
+    Ok("".to_string())
+}
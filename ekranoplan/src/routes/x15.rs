@@ -23,13 +23,12 @@ use axum::{
     routing::get,
 };
 use futures::Stream;
-use models::Channel;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 
 use crate::{
-    auth::get_user,
-    utils::{get_channel, get_event},
+    auth::get_session,
+    utils::{get_channels, get_event},
 };
 
 pub fn router() -> axum::Router<crate::GSt> {
@@ -40,32 +39,20 @@ pub async fn x15_handler(
     map: HeaderMap,
     State(state): State<crate::GSt>,
 ) -> Result<Sse<impl Stream<Item = Result<Event, crate::Error>>>, crate::Error> {
-    let (actor, account) = get_user(&map, &state.key, &state.pg).await?;
-    let rooms = sqlx::query_as!(
-        models::Room,
-        "SELECT * FROM rooms WHERE id IN (SELECT room_id FROM room_members WHERE actor_id = $1);",
-        actor.id
-    )
-    .fetch_all(&state.pg)
-    .await?;
-    let channels = futures::future::join_all(
-        rooms
-            .into_iter()
-            .map(|room| get_channel(&state.pg, room, Some(&actor))),
-    )
-    .await;
-
-    // turn channels from Vec<Result<_, Error>> to Result<Vec<_>, Error>
-    let channels: Result<Vec<Channel>, crate::Error> = channels.into_iter().collect();
-    let channels = channels?;
+    let (session_id, actor, account) = get_session(&map, &state.key, &state.pg).await?;
+    let channels = get_channels(&state.pg, &actor).await?;
 
     let stream = {
         let (sender, stream) = mpsc::channel(3_000);
         let mut consumants = state.consumants.write().await;
+        let consumant = crate::Consumant {
+            session_id,
+            sender: sender.clone(),
+        };
         if let Some(cons) = consumants.get_mut(&actor.id) {
-            cons.push(sender.clone());
+            cons.push(consumant);
         } else {
-            consumants.insert(actor.id.clone(), vec![sender.clone()]);
+            consumants.insert(actor.id.clone(), vec![consumant]);
         }
         sender
             .send(get_event(crate::X15Message::Ready {
@@ -14,141 +14,527 @@
    limitations under the License.
 */
 
-use std::{io::BufWriter, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use axum::response::sse::Event;
-use models::{Actor, Channel, ReadState, Room, RoomMember, Thread, Track, UserProfile};
-use sqlx::PgPool;
+use lazy_static::lazy_static;
+use models::{
+    Actor, Channel, Follow, Message, Reaction, ReadState, Room, RoomMember, Thread, Track,
+    UserProfile,
+};
+use regex::Regex;
+use serde::Deserialize;
+use sqlx::{PgConnection, PgPool, types::chrono};
 use tokio::sync::RwLock;
 
-use crate::{ConsumantsMap, X15Message};
+use crate::{
+    ConsumantsMap, X15Message,
+    routes::tracks::{LIKE_REACTION, REPOST_TRACK, THREAD_TRACK},
+    snow::SnowflakeGenerator,
+};
+
+lazy_static! {
+    // <@actor_id>
+    static ref MENTION_REGEX: Regex = Regex::new(r"<@(\d+)>").unwrap();
+}
+
+/// The ids of the actors mentioned in `content`, each once.
+pub fn mentions(content: &str) -> Vec<String> {
+    let mut mentioned: Vec<String> = MENTION_REGEX
+        .captures_iter(content)
+        .map(|c| c[1].to_string())
+        .collect();
+    mentioned.sort();
+    mentioned.dedup();
+    mentioned
+}
 
 pub async fn get_profile(pg: &PgPool, actor: Actor) -> Result<UserProfile, crate::Error> {
-    // fetch metadata
-    let followed_users = sqlx::query!(
-        "SELECT COUNT(followee_id) FROM follows WHERE follower_id = $1;",
-        &actor.id
-    )
-    .fetch_one(pg)
-    .await?;
-    let followers = sqlx::query!(
-        "SELECT COUNT(follower_id) FROM follows WHERE followee_id = $1;",
-        &actor.id
-    )
-    .fetch_one(pg)
-    .await?;
-    let tracks = sqlx::query!(
-        "SELECT COUNT(id) FROM tracks WHERE author_id = $1 AND parent_id IS NULL;",
-        &actor.id
+    Ok(get_profiles(pg, vec![actor])
+        .await?
+        .pop()
+        .expect("one profile per actor"))
+}
+
+/// Fills in the profile counts of every actor at once, keeping their order.
+pub async fn get_profiles(
+    pg: &PgPool,
+    actors: Vec<Actor>,
+) -> Result<Vec<UserProfile>, crate::Error> {
+    let ids: Vec<String> = actors.iter().map(|a| a.id.clone()).collect();
+
+    let counters: HashMap<String, (i64, i64, i64)> = sqlx::query!(
+        "SELECT * FROM actor_counters WHERE actor_id = ANY($1);",
+        &ids
     )
-    .fetch_one(pg)
-    .await?;
+    .fetch_all(pg)
+    .await?
+    .into_iter()
+    .map(|c| (c.actor_id, (c.followed, c.followers, c.tracks)))
+    .collect();
 
-    Ok(UserProfile {
-        actor,
-        followed: followed_users.count.unwrap_or(0),
-        followers: followers.count.unwrap_or(0),
-        tracks: tracks.count.unwrap_or(0),
-    })
+    Ok(actors
+        .into_iter()
+        .map(|actor| {
+            let (followed, followers, tracks) =
+                counters.get(&actor.id).copied().unwrap_or_default();
+            UserProfile {
+                actor,
+                followed,
+                followers,
+                tracks,
+            }
+        })
+        .collect())
 }
 
-pub async fn get_thread(
+/// Builds follower or following list entries from actor ids and when the
+/// follow started, keeping their order.
+pub async fn get_follows(
     pg: &PgPool,
-    track: Track,
-    get_children: bool,
-    me: &Option<Actor>,
-) -> Result<Thread, crate::Error> {
-    let children = if get_children {
-        // fetch a list of tracks
-        let children = sqlx::query_as!(
-            Track,
-            "SELECT * FROM tracks WHERE parent_id = $1;",
-            &track.id
-        )
+    follows: Vec<(String, i64)>,
+    viewer: &Option<Actor>,
+) -> Result<Vec<Follow>, crate::Error> {
+    let ids: Vec<String> = follows.iter().map(|(id, _)| id.clone()).collect();
+
+    let actors = sqlx::query_as!(Actor, "SELECT * FROM actors WHERE id = ANY($1);", &ids)
         .fetch_all(pg)
         .await?;
+    let mut profiles: HashMap<String, UserProfile> = get_profiles(pg, actors)
+        .await?
+        .into_iter()
+        .map(|p| (p.actor.id.clone(), p))
+        .collect();
 
-        // turn the tracks into threads
-        let children = futures::future::join_all(
-            children
-                .into_iter()
-                .map(|child| get_thread(pg, child, false, me)),
+    let follows_viewer: Option<HashSet<String>> = if let Some(viewer) = viewer {
+        Some(
+            sqlx::query!(
+                "SELECT follower_id FROM follows WHERE followee_id = $1 AND follower_id = ANY($2);",
+                viewer.id,
+                &ids
+            )
+            .fetch_all(pg)
+            .await?
+            .into_iter()
+            .map(|f| f.follower_id)
+            .collect(),
         )
-        .await;
-
-        // turn children from Vec<Result<Thread, Error>> to Result<Vec<Thread>, Error>
-        let children: Result<Vec<Thread>, crate::Error> = children.into_iter().collect();
-        Some(children?)
     } else {
         None
     };
 
-    let profile = if let Some(ref author_id) = track.author_id {
-        let user = sqlx::query_as!(Actor, "SELECT * FROM actors WHERE id = $1;", author_id)
-            .fetch_one(pg)
-            .await?;
-        Some(get_profile(pg, user).await?)
+    Ok(follows
+        .into_iter()
+        .filter_map(|(id, since)| {
+            Some(Follow {
+                follows_you: follows_viewer.as_ref().map(|f| f.contains(&id)),
+                profile: profiles.remove(&id)?,
+                since,
+            })
+        })
+        .collect())
+}
+
+pub async fn get_thread(
+    pg: &PgPool,
+    track: Track,
+    me: &Option<Actor>,
+) -> Result<Thread, crate::Error> {
+    Ok(get_threads(pg, vec![track], me)
+        .await?
+        .pop()
+        .expect("one thread per track"))
+}
+
+/// Turns tracks into threads with a fixed number of queries however many
+/// there are, keeping their order. Reposted and quoted tracks get embedded
+/// one level deep.
+pub async fn get_threads(
+    pg: &PgPool,
+    tracks: Vec<Track>,
+    me: &Option<Actor>,
+) -> Result<Vec<Thread>, crate::Error> {
+    let reference_ids: Vec<String> = tracks
+        .iter()
+        .filter_map(|t| t.reference_id.clone())
+        .collect();
+    let referenced = if reference_ids.is_empty() {
+        Vec::new()
     } else {
-        None
+        // tracks the viewer may not see stay hidden when reposted
+        sqlx::query_as!(
+            Track,
+            "SELECT * FROM tracks WHERE id = ANY($1) AND track_viewable(tracks, $2);",
+            &reference_ids,
+            me.as_ref().map(|m| m.id.clone())
+        )
+        .fetch_all(pg)
+        .await?
     };
-    let comments = sqlx::query!(
-        "SELECT COUNT(id) FROM tracks WHERE parent_id = $1;",
-        track.id
+
+    let ids: Vec<String> = tracks
+        .iter()
+        .chain(referenced.iter())
+        .map(|t| t.id.clone())
+        .collect();
+    let mut author_ids: Vec<String> = tracks
+        .iter()
+        .chain(referenced.iter())
+        .filter_map(|t| t.author_id.clone())
+        .collect();
+    author_ids.sort();
+    author_ids.dedup();
+
+    let authors = sqlx::query_as!(
+        Actor,
+        "SELECT * FROM actors WHERE id = ANY($1);",
+        &author_ids
     )
-    .fetch_one(pg)
+    .fetch_all(pg)
     .await?;
-    let likes = sqlx::query!(
-        "SELECT COUNT(user_id) FROM track_reactions WHERE track_id = $1;",
-        track.id
+    let profiles: HashMap<String, UserProfile> = get_profiles(pg, authors)
+        .await?
+        .into_iter()
+        .map(|p| (p.actor.id.clone(), p))
+        .collect();
+
+    // (comments, bookmarks, reposts, quotes)
+    let counters: HashMap<String, (i64, i64, i64, i64)> = sqlx::query!(
+        "SELECT * FROM track_counters WHERE track_id = ANY($1);",
+        &ids
     )
-    .fetch_one(pg)
-    .await?;
-    let bookmarks = sqlx::query!(
-        "SELECT COUNT(user_id) FROM track_bookmarks WHERE track_id = $1;",
-        track.id
+    .fetch_all(pg)
+    .await?
+    .into_iter()
+    .map(|c| (c.track_id, (c.comments, c.bookmarks, c.reposts, c.quotes)))
+    .collect();
+    let mut reactions: HashMap<String, Vec<Reaction>> = HashMap::new();
+    for r in sqlx::query!(
+        "SELECT * FROM track_reaction_counters WHERE track_id = ANY($1) AND reactions > 0 ORDER BY type;",
+        &ids
     )
-    .fetch_one(pg)
-    .await?;
+    .fetch_all(pg)
+    .await?
+    {
+        reactions.entry(r.track_id).or_default().push(Reaction {
+            r#type: r.r#type,
+            reactions: r.reactions,
+        });
+    }
 
-    let (bookmarked, liked) = if let Some(user) = me {
-        let bookmarked = sqlx::query!(
-            "SELECT * FROM track_bookmarks WHERE user_id = $1 AND track_id = $2;",
+    let viewer = if let Some(user) = me {
+        let bookmarked: HashSet<String> = sqlx::query!(
+            "SELECT track_id FROM track_bookmarks WHERE user_id = $1 AND track_id = ANY($2);",
             user.id,
-            &track.id
+            &ids
         )
-        .fetch_optional(pg)
+        .fetch_all(pg)
         .await?
-        .is_some();
+        .into_iter()
+        .map(|b| b.track_id)
+        .collect();
+        let mut reacted: HashMap<String, Vec<i64>> = HashMap::new();
+        for r in sqlx::query!(
+            "SELECT track_id, type FROM track_reactions WHERE user_id = $1 AND track_id = ANY($2) ORDER BY type;",
+            user.id,
+            &ids
+        )
+        .fetch_all(pg)
+        .await?
+        {
+            reacted.entry(r.track_id).or_default().push(r.r#type);
+        }
+        let reposted: HashSet<String> = sqlx::query!(
+            r#"SELECT reference_id AS "id!" FROM tracks WHERE author_id = $1 AND type = $2 AND reference_id = ANY($3);"#,
+            user.id,
+            REPOST_TRACK,
+            &ids
+        )
+        .fetch_all(pg)
+        .await?
+        .into_iter()
+        .map(|r| r.id)
+        .collect();
+        Some((bookmarked, reacted, reposted))
+    } else {
+        None
+    };
+
+    let thread = |track: Track| {
+        let reactions = reactions.get(&track.id).cloned().unwrap_or_default();
+        let (comments, bookmarks, reposts, quotes) =
+            counters.get(&track.id).copied().unwrap_or_default();
+        let (bookmarked, reacted, reposted) = match viewer {
+            Some((ref bookmarked, ref reacted, ref reposted)) => (
+                Some(bookmarked.contains(&track.id)),
+                Some(reacted.get(&track.id).cloned().unwrap_or_default()),
+                Some(reposted.contains(&track.id)),
+            ),
+            None => (None, None, None),
+        };
+        Thread {
+            profile: track
+                .author_id
+                .as_ref()
+                .and_then(|id| profiles.get(id).cloned()),
+            likes: reactions
+                .iter()
+                .find(|r| r.r#type == LIKE_REACTION)
+                .map_or(0, |r| r.reactions),
+            reactions,
+            comments,
+            bookmarks,
+            reposts,
+            quotes,
+            bookmarked,
+            reacted,
+            reposted,
+            hidden: None,
+            children: None,
+            ancestors: None,
+            reference: None,
+            track,
+        }
+    };
+
+    let referenced: HashMap<String, Thread> = referenced
+        .into_iter()
+        .map(|t| (t.id.clone(), thread(t)))
+        .collect();
+    Ok(tracks
+        .into_iter()
+        .map(|track| {
+            let reference = track
+                .reference_id
+                .as_ref()
+                .and_then(|id| referenced.get(id).cloned())
+                .map(Box::new);
+            Thread {
+                reference,
+                ..thread(track)
+            }
+        })
+        .collect())
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplySort {
+    /// Newest replies first.
+    #[default]
+    New,
+    /// Oldest replies first.
+    Old,
+    /// Most reactions and replies first.
+    Top,
+}
+
+impl ReplySort {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ReplySort::New => "new",
+            ReplySort::Old => "old",
+            ReplySort::Top => "top",
+        }
+    }
+}
+
+/// Upper bound on the replies loaded into one tree, however wide or deep.
+const MAX_TREE_NODES: i64 = 500;
+
+/// How much of a reply tree to load. `limit` applies to the replies of
+/// every track in the tree, `offset` only to the direct replies of the root.
+/// Levels stop being loaded once the tree would outgrow `MAX_TREE_NODES`.
+#[derive(Debug, Clone, Copy)]
+pub struct TreeOptions {
+    pub depth: i32,
+    pub limit: i64,
+    pub offset: i64,
+    pub sort: ReplySort,
+}
+
+/// Loads `track` with its replies down to `options.depth` levels, and the
+/// chain of tracks it replies to up to the root. Tracks at the deepest level
+/// loaded have no `children` so clients can tell them apart from ones
+/// without replies. Replies `me` may not see, like ones by actors who blocked
+/// them or by locked actors they don't follow, are left out with theirs,
+/// while ancestors they may not see are replaced by [`hidden_thread`]s.
+pub async fn get_thread_tree(
+    pg: &PgPool,
+    track: Track,
+    options: TreeOptions,
+    me: &Option<Actor>,
+) -> Result<Thread, crate::Error> {
+    let viewer = me.as_ref().map(|m| m.id.clone());
 
-        let reaction = sqlx::query!(
-            "SELECT user_id FROM track_reactions WHERE track_id = $1 AND user_id = $2;",
-            &track.id,
-            user.id
+    // the recursion walks the tree a level at a time, so stopping it once
+    // there are more than MAX_TREE_NODES replies leaves only the last level
+    // partly loaded
+    let rows = sqlx::query!(
+        r#"WITH RECURSIVE tree AS (
+            SELECT $1::TEXT AS id, NULL::TEXT AS parent_id, 0 AS depth, 0::BIGINT AS rank
+            UNION ALL
+            SELECT child.id, child.parent_id, tree.depth + 1, child.rank
+            FROM tree, LATERAL (
+                SELECT id, parent_id, ROW_NUMBER() OVER (ORDER BY
+                    CASE WHEN $5::TEXT = 'top' THEN
+                        COALESCE((SELECT SUM(reactions) FROM track_reaction_counters WHERE track_id = tracks.id), 0)
+                        + COALESCE((SELECT comments FROM track_counters WHERE track_id = tracks.id), 0)
+                    END DESC,
+                    CASE WHEN $5::TEXT = 'old' THEN tracks.original_ts END ASC,
+                    tracks.original_ts DESC
+                ) AS rank
+                FROM tracks WHERE tracks.parent_id = tree.id AND track_viewable(tracks, $2)
+                ORDER BY rank LIMIT $3 OFFSET CASE WHEN tree.depth = 0 THEN $4::BIGINT ELSE 0 END
+            ) AS child
+            WHERE tree.depth < $6
         )
-        .fetch_optional(pg)
+        SELECT id AS "id!", parent_id AS "parent_id!", depth AS "depth!", rank AS "rank!"
+        FROM tree WHERE depth > 0 LIMIT $7;"#,
+        &track.id,
+        viewer,
+        options.limit,
+        options.offset,
+        options.sort.as_str(),
+        options.depth,
+        MAX_TREE_NODES + 1
+    )
+    .fetch_all(pg)
+    .await?;
+
+    let mut loaded_depth = rows.iter().map(|r| r.depth).max().unwrap_or(0);
+    // a level that doesn't fit is left out whole, so no track ends up with
+    // only some of its replies
+    if rows.len() as i64 > MAX_TREE_NODES {
+        loaded_depth -= 1;
+    }
+    let mut replies: Vec<(String, String, i32, i64)> = rows
+        .into_iter()
+        .filter(|r| r.depth <= loaded_depth)
+        .map(|r| (r.id, r.parent_id, r.depth, r.rank))
+        .collect();
+    replies.sort_by_key(|(_, _, depth, rank)| (*depth, *rank));
+    let ancestors = if let Some(ref parent_id) = track.parent_id {
+        let chain = sqlx::query!(
+            r#"WITH RECURSIVE chain AS (
+                SELECT id, parent_id, 1 AS depth FROM tracks WHERE id = $1
+                UNION ALL
+                SELECT tracks.id, tracks.parent_id, chain.depth + 1 FROM tracks JOIN chain ON tracks.id = chain.parent_id WHERE chain.depth < 100
+            )
+            SELECT chain.id AS "id!", chain.parent_id, track_viewable(tracks, $2) AS "viewable!"
+            FROM chain JOIN tracks ON tracks.id = chain.id
+            ORDER BY chain.depth DESC;"#,
+            parent_id,
+            viewer
+        )
+        .fetch_all(pg)
         .await?;
-        (Some(bookmarked), Some(reaction.is_some()))
+        let ids: Vec<String> = chain
+            .iter()
+            .filter(|a| a.viewable)
+            .map(|a| a.id.clone())
+            .collect();
+        let mut threads = get_threads_by_id(pg, &ids, me).await?;
+        Some(
+            chain
+                .into_iter()
+                .map(|a| {
+                    threads
+                        .remove(&a.id)
+                        .unwrap_or_else(|| hidden_thread(a.id, a.parent_id))
+                })
+                .collect::<Vec<Thread>>(),
+        )
     } else {
-        (None, None)
+        None
     };
 
-    Ok(Thread {
-        track,
-        children,
-        profile,
-        bookmarked,
-        liked,
-        comments: comments.count.unwrap_or(0),
-        likes: likes.count.unwrap_or(0),
-        bookmarks: bookmarks.count.unwrap_or(0),
-    })
+    let ids: Vec<String> = replies.iter().map(|(id, _, _, _)| id.clone()).collect();
+    let mut threads = get_threads_by_id(pg, &ids, me).await?;
+
+    // replies are ordered by depth, so every parent is placed before its
+    // children and building bottom up only needs the reverse
+    let mut children: HashMap<String, Vec<Thread>> = HashMap::new();
+    for (id, parent_id, depth, _) in replies.iter().rev() {
+        if let Some(mut thread) = threads.remove(id) {
+            if *depth < loaded_depth {
+                let mut replies = children.remove(id).unwrap_or_default();
+                replies.reverse();
+                thread.children = Some(replies);
+            }
+            children.entry(parent_id.clone()).or_default().push(thread);
+        }
+    }
+
+    let mut thread = get_thread(pg, track, me).await?;
+    let mut replies = children.remove(&thread.track.id).unwrap_or_default();
+    replies.reverse();
+    thread.children = Some(replies);
+    thread.ancestors = ancestors;
+    Ok(thread)
+}
+
+/// Stands in for an ancestor the viewer may not see, keeping only where it
+/// sits in the chain.
+fn hidden_thread(id: String, parent_id: Option<String>) -> Thread {
+    Thread {
+        track: Track {
+            id,
+            r#type: THREAD_TRACK,
+            author_id: None,
+            content: String::new(),
+            original_ts: 0,
+            indexed_ts: 0,
+            parent_id,
+            signature: String::new(),
+            reference_id: None,
+            edited_ts: None,
+            signature_version: 0,
+            visibility: 0,
+            reply_policy: 0,
+        },
+        profile: None,
+        likes: 0,
+        reactions: Vec::new(),
+        comments: 0,
+        bookmarks: 0,
+        reposts: 0,
+        quotes: 0,
+        bookmarked: None,
+        reacted: None,
+        reposted: None,
+        hidden: Some(true),
+        children: None,
+        ancestors: None,
+        reference: None,
+    }
+}
+
+async fn get_threads_by_id(
+    pg: &PgPool,
+    ids: &[String],
+    me: &Option<Actor>,
+) -> Result<HashMap<String, Thread>, crate::Error> {
+    let tracks = sqlx::query_as!(Track, "SELECT * FROM tracks WHERE id = ANY($1);", ids)
+        .fetch_all(pg)
+        .await?;
+    Ok(get_threads(pg, tracks, me)
+        .await?
+        .into_iter()
+        .map(|thread| (thread.track.id.clone(), thread))
+        .collect())
 }
 
+/// Encodes `data` for the X15 stream. Events are CBOR, which is binary, so
+/// they're sent as base64 as SSE only carries text.
 #[inline(always)]
 pub fn get_event(data: crate::X15Message) -> Result<Event, crate::Error> {
-    let mut writer = BufWriter::new(Vec::new());
-    ciborium::into_writer(&data, &mut writer)?;
-    Ok(Event::default().data(String::from_utf8(writer.buffer().to_vec())?))
+    let mut buf = Vec::new();
+    ciborium::into_writer(&data, &mut buf)?;
+    Ok(Event::default().data(vodozemac::base64_encode(buf)))
 }
 
 #[inline(always)]
@@ -187,43 +573,153 @@ pub async fn get_channel(
     let members: Result<Vec<Actor>, crate::Error> = members.into_iter().collect();
     let members = members?;
 
-    let state = if let Some(actor) = actor {
-        sqlx::query_as!(
+    let (state, unread) = if let Some(actor) = actor {
+        let state = sqlx::query_as!(
             ReadState,
             "SELECT * FROM read_states WHERE room_id = $1 AND user_id = $2;",
             room.id,
             actor.id
         )
         .fetch_optional(pg)
-        .await?
+        .await?;
+        let last_read = state
+            .as_ref()
+            .and_then(|s| s.last_message_id.as_ref())
+            .and_then(|id| id.parse::<i64>().ok())
+            .unwrap_or(0);
+        let unread = sqlx::query!(
+            "SELECT COUNT(id) FROM messages WHERE room_id = $1 AND author_id IS DISTINCT FROM $2 AND CAST(id AS BIGINT) > $3;",
+            room.id,
+            actor.id,
+            last_read
+        )
+        .fetch_one(pg)
+        .await?;
+        (state, Some(unread.count.unwrap_or(0)))
     } else {
-        None
+        (None, None)
     };
 
     Ok(Channel {
         room,
         members,
         state,
+        unread,
     })
 }
 
+pub async fn get_channels(pg: &PgPool, actor: &Actor) -> Result<Vec<Channel>, crate::Error> {
+    let rooms = sqlx::query_as!(
+        Room,
+        "SELECT * FROM rooms WHERE id IN (SELECT room_id FROM room_members WHERE actor_id = $1);",
+        actor.id
+    )
+    .fetch_all(pg)
+    .await?;
+    let channels = futures::future::join_all(
+        rooms
+            .into_iter()
+            .map(|room| get_channel(pg, room, Some(actor))),
+    )
+    .await;
+
+    // turn channels from Vec<Result<_, Error>> to Result<Vec<_>, Error>
+    channels.into_iter().collect()
+}
+
+pub async fn create_message(
+    conn: &mut PgConnection,
+    snow: &SnowflakeGenerator,
+    room_id: &str,
+    author_id: &str,
+    content: &str,
+    session_id: Option<&str>,
+    device_id: Option<&str>,
+) -> Result<Message, crate::Error> {
+    let message_id = snow.generate().unwrap().to_string();
+    let ts = chrono::Utc::now().timestamp_millis();
+
+    let msg = sqlx::query_as!(
+        Message,
+        "INSERT INTO messages (id, room_id, author_id, content, timestamp, session_id, device_id) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *;",
+        message_id,
+        room_id,
+        author_id,
+        content,
+        ts,
+        session_id,
+        device_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    sqlx::query!(
+        "UPDATE rooms SET last_message_id = $1 WHERE id = $2;",
+        msg.id,
+        room_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // bump the mention counter of every other member this message mentions,
+    // unless they muted the author, which for encrypted messages is up to
+    // the clients
+    let mentioned: Vec<String> = if session_id.is_none() {
+        mentions(content)
+    } else {
+        Vec::new()
+    };
+    if !mentioned.is_empty() {
+        sqlx::query!(
+            "INSERT INTO read_states (user_id, room_id, mentions) SELECT actor_id, room_id, 1 FROM room_members WHERE room_id = $1 AND actor_id = ANY($2) AND actor_id != $3 AND actor_id IN (SELECT id FROM accounts) AND actor_id NOT IN (SELECT muter_id FROM mutes WHERE muted_id = $3) ON CONFLICT (user_id, room_id) DO UPDATE SET mentions = read_states.mentions + 1;",
+            room_id,
+            &mentioned,
+            author_id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(msg)
+}
+
+/// Sends `event` to every stream of `subjects`. This runs after whatever
+/// the event is about has been committed, so failures are only logged.
 pub async fn send_event(
     consumants: &Arc<RwLock<ConsumantsMap>>,
     subjects: Vec<&str>,
     event: X15Message,
-) -> Result<(), crate::Error> {
+) {
+    let real_event = match get_event(event) {
+        Ok(event) => event,
+        Err(err) => {
+            eprintln!("failed to encode event: {err:?}");
+            return;
+        }
+    };
     let consumants = consumants.read().await;
-    let real_event = get_event(event)?;
     for subject in subjects {
         if let Some(c) = consumants.get(subject) {
-            for sender in c {
-                sender
-                    .send(Ok(real_event.clone()))
-                    .await
-                    .map_err(|_| crate::Error::SendError)?;
+            for consumant in c {
+                // the stream was closed and is yet to be dropped
+                if consumant.sender.send(Ok(real_event.clone())).await.is_err() {
+                    eprintln!("failed to send event to session {}", consumant.session_id);
+                }
             }
         }
     }
+}
 
-    Ok(())
+/// Drops the X15 streams opened with any of `session_ids`, which ends them.
+pub async fn close_streams(
+    consumants: &Arc<RwLock<ConsumantsMap>>,
+    actor_id: &str,
+    session_ids: &[String],
+) {
+    let mut consumants = consumants.write().await;
+    if let Some(c) = consumants.get_mut(actor_id) {
+        c.retain(|consumant| !session_ids.contains(&consumant.session_id));
+        if c.is_empty() {
+            consumants.remove(actor_id);
+        }
+    }
 }
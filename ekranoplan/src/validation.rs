@@ -0,0 +1,166 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+use std::collections::BTreeMap;
+
+use axum::{
+    Json, async_trait,
+    extract::{FromRequest, Request, rejection::JsonRejection},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, de::DeserializeOwned};
+use serde_valid::{
+    Validate,
+    validation::{Error, Errors},
+};
+
+use crate::error::ErrorBody;
+
+lazy_static! {
+    // deliberately loose, the verification mail is the real check
+    static ref EMAIL_REGEX: Regex = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap();
+}
+
+pub fn email(email: &str) -> Result<(), Error> {
+    if EMAIL_REGEX.is_match(email) {
+        Ok(())
+    } else {
+        Err(Error::Custom("Invalid email address".to_string()))
+    }
+}
+
+/// Passwords must mix letters with digits or symbols on top of their length.
+pub fn password(password: &str) -> Result<(), Error> {
+    let letters = password.chars().any(char::is_alphabetic);
+    let others = password.chars().any(|c| !c.is_alphabetic());
+    if letters && others {
+        Ok(())
+    } else {
+        Err(Error::Custom(
+            "Password must contain both letters and numbers or symbols".to_string(),
+        ))
+    }
+}
+
+pub fn not_blank(text: &str) -> Result<(), Error> {
+    if text.trim().is_empty() {
+        Err(Error::Custom("Must not be blank".to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Applies `rule` to fields which may be left out.
+pub fn optional(value: &Option<String>, rule: fn(&str) -> Result<(), Error>) -> Result<(), Error> {
+    value.as_deref().map_or(Ok(()), rule)
+}
+
+/// For fields which may be left out, or set to null to clear them. Serde
+/// reads both as `None` on its own, this reads null as `Some(None)`.
+pub fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Like [`Json`], but also runs the body's validation rules.
+pub struct ValidJson<T>(pub T);
+
+pub enum ValidJsonRejection {
+    Json(JsonRejection),
+    Invalid(Errors),
+}
+
+impl IntoResponse for ValidJsonRejection {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Json(rejection) => crate::Error::from(rejection).into_response(),
+            Self::Invalid(errors) => {
+                let mut fields = BTreeMap::new();
+                flatten(&mut fields, String::new(), &errors);
+                ErrorBody {
+                    code: "invalid_body",
+                    status: StatusCode::BAD_REQUEST.as_u16(),
+                    message: "Invalid body".to_string(),
+                    fields: Some(fields),
+                    correlation_id: None,
+                }
+                .into_response()
+            }
+        }
+    }
+}
+
+/// Collects every message under its field path, e.g. `email` or `items.0`.
+/// Errors about the body as a whole end up under an empty key.
+fn flatten(fields: &mut BTreeMap<String, Vec<String>>, path: String, errors: &Errors) {
+    let (own, children): (&Vec<Error>, Vec<(String, &Errors)>) = match errors {
+        Errors::NewType(errors) => (errors, Vec::new()),
+        Errors::Object(object) => (
+            &object.errors,
+            object
+                .properties
+                .iter()
+                .map(|(key, errors)| (key.clone(), errors))
+                .collect(),
+        ),
+        Errors::Array(array) => (
+            &array.errors,
+            array
+                .items
+                .iter()
+                .map(|(index, errors)| (index.to_string(), errors))
+                .collect(),
+        ),
+    };
+
+    if !own.is_empty() {
+        fields
+            .entry(path.clone())
+            .or_default()
+            .extend(own.iter().map(|e| e.to_string()));
+    }
+    for (key, errors) in children {
+        let child = if path.is_empty() {
+            key
+        } else {
+            format!("{}.{}", path, key)
+        };
+        flatten(fields, child, errors);
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = ValidJsonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(ValidJsonRejection::Json)?;
+        value.validate().map_err(ValidJsonRejection::Invalid)?;
+        Ok(ValidJson(value))
+    }
+}
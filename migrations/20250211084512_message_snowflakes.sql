@@ -0,0 +1,19 @@
+-- messages used to be keyed by a blake3 hash, rekey them as snowflakes
+-- built from their timestamp so that history can be ordered by id.
+CREATE TEMPORARY TABLE message_rekeys AS
+SELECT
+    id AS old_id,
+    (((timestamp - 1649325271415) << 22) + ROW_NUMBER() OVER (PARTITION BY timestamp ORDER BY id) - 1)::TEXT AS new_id
+FROM messages
+WHERE id !~ '^[0-9]+$';
+
+UPDATE rooms SET last_message_id = r.new_id
+FROM message_rekeys r WHERE rooms.last_message_id = r.old_id;
+UPDATE read_states SET last_message_id = r.new_id
+FROM message_rekeys r WHERE read_states.last_message_id = r.old_id;
+UPDATE messages SET id = r.new_id
+FROM message_rekeys r WHERE messages.id = r.old_id;
+
+DROP TABLE message_rekeys;
+
+CREATE INDEX IF NOT EXISTS messages_history ON messages (room_id, (CAST(id AS BIGINT)));
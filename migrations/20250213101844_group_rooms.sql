@@ -0,0 +1,4 @@
+-- 0: Direct Message
+-- 1: Group
+ALTER TABLE rooms
+ADD owner_id TEXT REFERENCES actors(id) ON DELETE SET NULL;
@@ -0,0 +1,42 @@
+CREATE TABLE IF NOT EXISTS devices (
+    id TEXT NOT NULL,
+    actor_id TEXT NOT NULL REFERENCES actors(id) ON DELETE CASCADE,
+    -- curve25519
+    identity_key TEXT NOT NULL,
+    -- ed25519
+    signing_key TEXT NOT NULL,
+    -- signing_key over "actor_id:device_id:identity_key"
+    signature TEXT NOT NULL,
+    PRIMARY KEY (actor_id, id)
+);
+
+CREATE TABLE IF NOT EXISTS one_time_keys (
+    actor_id TEXT NOT NULL,
+    device_id TEXT NOT NULL,
+    key_id TEXT NOT NULL,
+    key TEXT NOT NULL,
+    PRIMARY KEY (actor_id, device_id, key_id),
+    FOREIGN KEY (actor_id, device_id) REFERENCES devices(actor_id, id) ON DELETE CASCADE
+);
+
+-- olm encrypted messages waiting to be picked up by a device,
+-- mostly megolm room keys.
+CREATE TABLE IF NOT EXISTS device_messages (
+    id TEXT NOT NULL PRIMARY KEY,
+    actor_id TEXT NOT NULL,
+    device_id TEXT NOT NULL,
+    sender_id TEXT NOT NULL REFERENCES actors(id) ON DELETE CASCADE,
+    sender_device TEXT NOT NULL,
+    -- 0: Pre-Key
+    -- 1: Normal
+    type INTEGER NOT NULL,
+    body TEXT NOT NULL,
+    FOREIGN KEY (actor_id, device_id) REFERENCES devices(actor_id, id) ON DELETE CASCADE
+);
+
+-- when set, content is a base64 megolm message from this session and device
+ALTER TABLE messages
+ADD session_id TEXT;
+
+ALTER TABLE messages
+ADD device_id TEXT;
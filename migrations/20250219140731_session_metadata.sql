@@ -0,0 +1,11 @@
+ALTER TABLE sessions
+ADD created_at BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE sessions
+ADD last_seen BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE sessions
+ADD user_agent TEXT;
+
+ALTER TABLE sessions
+ADD ip TEXT;
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    -- blake3 of the token handed to the client
+    token_hash TEXT NOT NULL PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    -- set once the token has been exchanged, presenting it again
+    -- means it leaked and the whole session is revoked
+    used BOOLEAN NOT NULL DEFAULT false,
+    expires_at BIGINT NOT NULL,
+    FOREIGN KEY (session_id, user_id) REFERENCES sessions(id, user_id) ON DELETE CASCADE
+);
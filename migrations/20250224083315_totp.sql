@@ -0,0 +1,13 @@
+-- base32, only trusted once totp_enabled is set
+ALTER TABLE accounts
+ADD totp_secret TEXT;
+
+ALTER TABLE accounts
+ADD totp_enabled BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE IF NOT EXISTS recovery_codes (
+    user_id TEXT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    -- blake3 of the code
+    code_hash TEXT NOT NULL,
+    PRIMARY KEY (user_id, code_hash)
+);
@@ -0,0 +1,13 @@
+ALTER TABLE accounts
+ADD email_verified BOOLEAN NOT NULL DEFAULT false;
+
+-- outstanding email verification and password reset tokens,
+-- deleted as soon as they are used
+CREATE TABLE IF NOT EXISTS email_tokens (
+    id TEXT NOT NULL PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    -- 0: Verify Email
+    -- 1: Reset Password
+    purpose INTEGER NOT NULL,
+    expires_at BIGINT NOT NULL
+);
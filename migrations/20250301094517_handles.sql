@@ -0,0 +1,4 @@
+-- when the account last claimed or changed its handle,
+-- used to rate limit handle changes
+ALTER TABLE accounts
+ADD handle_changed_at BIGINT;
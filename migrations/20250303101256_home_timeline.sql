@@ -0,0 +1,2 @@
+-- root tracks by author, newest first, for the home timeline
+CREATE INDEX IF NOT EXISTS tracks_by_author ON tracks (author_id, indexed_ts DESC) WHERE parent_id IS NULL;
@@ -0,0 +1,7 @@
+-- the track a repost (type 1) or quote (type 2) refers to
+ALTER TABLE tracks
+ADD reference_id TEXT REFERENCES tracks(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS tracks_references ON tracks (reference_id, type);
+-- an actor can only repost a track once
+CREATE UNIQUE INDEX IF NOT EXISTS tracks_reposts ON tracks (author_id, reference_id) WHERE type = 1;
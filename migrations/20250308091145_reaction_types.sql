@@ -0,0 +1,9 @@
+-- values below 8 are built in reactions, 0 being a like,
+-- anything else is the id of a custom emoji
+ALTER TABLE track_reactions
+ADD type BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE track_reactions
+DROP CONSTRAINT track_reactions_pkey;
+ALTER TABLE track_reactions
+ADD PRIMARY KEY (track_id, user_id, type);
@@ -0,0 +1,12 @@
+ALTER TABLE tracks
+ADD edited_ts BIGINT;
+
+-- earlier versions of edited tracks, each with the signature
+-- made over it and the timestamp that signature covers
+CREATE TABLE IF NOT EXISTS track_revisions (
+    track_id TEXT NOT NULL REFERENCES tracks(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    ts BIGINT NOT NULL,
+    PRIMARY KEY (track_id, ts)
+);
@@ -0,0 +1,6 @@
+-- which signing payload the signature is made over,
+-- 0 being the original bare concatenation
+ALTER TABLE tracks
+ADD signature_version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE track_revisions
+ADD signature_version INTEGER NOT NULL DEFAULT 0;
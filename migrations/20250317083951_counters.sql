@@ -0,0 +1,37 @@
+-- maintained alongside the rows they count so profiles and threads
+-- don't have to count on every read, see counters.rs
+CREATE TABLE IF NOT EXISTS actor_counters (
+    actor_id TEXT NOT NULL PRIMARY KEY REFERENCES actors(id) ON DELETE CASCADE,
+    followed BIGINT NOT NULL DEFAULT 0,
+    followers BIGINT NOT NULL DEFAULT 0,
+    tracks BIGINT NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS track_counters (
+    track_id TEXT NOT NULL PRIMARY KEY REFERENCES tracks(id) ON DELETE CASCADE,
+    comments BIGINT NOT NULL DEFAULT 0,
+    bookmarks BIGINT NOT NULL DEFAULT 0,
+    reposts BIGINT NOT NULL DEFAULT 0,
+    quotes BIGINT NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS track_reaction_counters (
+    track_id TEXT NOT NULL REFERENCES tracks(id) ON DELETE CASCADE,
+    type BIGINT NOT NULL,
+    reactions BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (track_id, type)
+);
+
+INSERT INTO actor_counters (actor_id, followed, followers, tracks)
+SELECT id,
+    (SELECT COUNT(*) FROM follows WHERE follower_id = actors.id),
+    (SELECT COUNT(*) FROM follows WHERE followee_id = actors.id),
+    (SELECT COUNT(*) FROM tracks WHERE author_id = actors.id AND parent_id IS NULL)
+FROM actors;
+INSERT INTO track_counters (track_id, comments, bookmarks, reposts, quotes)
+SELECT id,
+    (SELECT COUNT(*) FROM tracks AS t WHERE t.parent_id = tracks.id),
+    (SELECT COUNT(*) FROM track_bookmarks WHERE track_id = tracks.id),
+    (SELECT COUNT(*) FROM tracks AS t WHERE t.reference_id = tracks.id AND t.type = 1),
+    (SELECT COUNT(*) FROM tracks AS t WHERE t.reference_id = tracks.id AND t.type = 2)
+FROM tracks;
+INSERT INTO track_reaction_counters (track_id, type, reactions)
+SELECT track_id, type, COUNT(*) FROM track_reactions GROUP BY track_id, type;
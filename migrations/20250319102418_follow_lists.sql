@@ -0,0 +1,2 @@
+CREATE INDEX IF NOT EXISTS follows_followers ON follows (followee_id, since DESC);
+CREATE INDEX IF NOT EXISTS follows_following ON follows (follower_id, since DESC);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS blocks (
+    blocker_id TEXT NOT NULL REFERENCES actors(id) ON DELETE CASCADE,
+    blocked_id TEXT NOT NULL REFERENCES actors(id) ON DELETE CASCADE,
+    since BIGINT NOT NULL,
+    PRIMARY KEY (blocker_id, blocked_id)
+);
+CREATE INDEX IF NOT EXISTS blocks_blocked ON blocks (blocked_id);
+CREATE TABLE IF NOT EXISTS mutes (
+    muter_id TEXT NOT NULL REFERENCES actors(id) ON DELETE CASCADE,
+    muted_id TEXT NOT NULL REFERENCES actors(id) ON DELETE CASCADE,
+    since BIGINT NOT NULL,
+    PRIMARY KEY (muter_id, muted_id)
+);
+CREATE INDEX IF NOT EXISTS mutes_muted ON mutes (muted_id);
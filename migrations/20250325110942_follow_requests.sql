@@ -0,0 +1,9 @@
+ALTER TABLE actors ADD COLUMN IF NOT EXISTS locked BOOLEAN NOT NULL DEFAULT false;
+CREATE TABLE IF NOT EXISTS follow_requests (
+    requester_id TEXT NOT NULL REFERENCES actors(id) ON DELETE CASCADE,
+    target_id TEXT NOT NULL REFERENCES actors(id) ON DELETE CASCADE,
+    since BIGINT NOT NULL,
+    PRIMARY KEY (requester_id, target_id)
+);
+CREATE INDEX IF NOT EXISTS follow_requests_incoming ON follow_requests (target_id, since DESC);
+CREATE INDEX IF NOT EXISTS follow_requests_outgoing ON follow_requests (requester_id, since DESC);
@@ -0,0 +1,20 @@
+-- see the *_VISIBILITY and REPLY_* constants in routes/tracks/mod.rs
+ALTER TABLE tracks ADD COLUMN IF NOT EXISTS visibility INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE tracks ADD COLUMN IF NOT EXISTS reply_policy INTEGER NOT NULL DEFAULT 0;
+CREATE TABLE IF NOT EXISTS track_mentions (
+    track_id TEXT NOT NULL REFERENCES tracks(id) ON DELETE CASCADE,
+    actor_id TEXT NOT NULL REFERENCES actors(id) ON DELETE CASCADE,
+    PRIMARY KEY (track_id, actor_id)
+);
+CREATE INDEX IF NOT EXISTS track_mentions_actor ON track_mentions (actor_id);
+-- whether viewer, which is NULL when signed out, may see track
+CREATE OR REPLACE FUNCTION track_visible(track tracks, viewer TEXT) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT COALESCE(
+        track.visibility IN (0, 1)
+        OR track.author_id = viewer
+        OR EXISTS (SELECT 1 FROM track_mentions WHERE track_id = track.id AND actor_id = viewer)
+        OR (track.visibility = 2 AND EXISTS (SELECT 1 FROM follows WHERE follower_id = viewer AND followee_id = track.author_id)),
+        false
+    );
+$$;
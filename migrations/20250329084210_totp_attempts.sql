@@ -0,0 +1,14 @@
+-- login challenges are only good while they're stored here, so they
+-- can be used up after too many wrong codes
+CREATE TABLE IF NOT EXISTS mfa_challenges (
+    id TEXT NOT NULL PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    expires_at BIGINT NOT NULL,
+    failures INTEGER NOT NULL DEFAULT 0
+);
+
+-- wrong codes across every challenge, reset once a code is accepted
+ALTER TABLE accounts ADD COLUMN IF NOT EXISTS totp_failures INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE accounts ADD COLUMN IF NOT EXISTS totp_failed_at BIGINT;
+-- the time step of the last accepted code, which may not be used again
+ALTER TABLE accounts ADD COLUMN IF NOT EXISTS totp_last_step BIGINT;
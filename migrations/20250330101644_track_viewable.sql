@@ -0,0 +1,13 @@
+-- everything that decides whether viewer, which is NULL when signed out,
+-- may see track: its visibility, whether its author blocked the viewer
+-- and whether its author is locked and not followed by the viewer
+CREATE OR REPLACE FUNCTION track_viewable(track tracks, viewer TEXT) RETURNS BOOLEAN
+LANGUAGE sql STABLE AS $$
+    SELECT track_visible(track, viewer)
+        AND NOT EXISTS (SELECT 1 FROM blocks WHERE blocker_id = track.author_id AND blocked_id = viewer)
+        AND NOT EXISTS (
+            SELECT 1 FROM actors WHERE actors.id = track.author_id AND actors.locked
+                AND actors.id IS DISTINCT FROM viewer
+                AND NOT EXISTS (SELECT 1 FROM follows WHERE follower_id = viewer AND followee_id = actors.id)
+        );
+$$;
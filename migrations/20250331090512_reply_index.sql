@@ -0,0 +1,2 @@
+-- replies by parent, for loading reply trees and counting comments
+CREATE INDEX IF NOT EXISTS tracks_replies ON tracks (parent_id, indexed_ts);
@@ -0,0 +1,2 @@
+-- bookmarks by track, for recounting them
+CREATE INDEX IF NOT EXISTS track_bookmarks_track ON track_bookmarks (track_id);
@@ -0,0 +1,4 @@
+-- when each member joined, so ownership passes to whoever has been there
+-- longest. Members from before this all count as joining now
+ALTER TABLE room_members
+ADD joined_at BIGINT NOT NULL DEFAULT (EXTRACT(EPOCH FROM NOW()) * 1000)::BIGINT;
@@ -17,12 +17,15 @@
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 
+pub mod signing;
+
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
 pub struct Account {
     pub id: String,
     #[sqlx(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub email: Option<String>,
+    pub email_verified: bool,
     #[sqlx(default)]
     #[serde(skip_serializing)]
     pub password: String,
@@ -30,6 +33,27 @@ pub struct Account {
     pub theme: String,
     #[serde(skip_serializing)]
     pub pickle: String,
+    #[serde(skip_serializing)]
+    pub totp_secret: Option<String>,
+    pub totp_enabled: bool,
+    #[serde(skip_serializing)]
+    pub totp_failures: i32,
+    #[serde(skip_serializing)]
+    pub totp_failed_at: Option<i64>,
+    #[serde(skip_serializing)]
+    pub totp_last_step: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handle_changed_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Session {
+    pub id: String,
+    pub user_id: String,
+    pub created_at: i64,
+    pub last_seen: i64,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -40,6 +64,16 @@ pub struct UserProfile {
     pub tracks: i64,
 }
 
+/// An entry in a follower or following list.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Follow {
+    pub profile: UserProfile,
+    pub since: i64,
+    /// Whether this actor follows the viewer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follows_you: Option<bool>,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
 pub struct Actor {
     pub id: String,
@@ -51,6 +85,8 @@ pub struct Actor {
     pub bio: Option<String>,
     pub status: Option<String>,
     pub public_key: String,
+    /// Whether follows have to be approved by this actor first.
+    pub locked: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -69,6 +105,30 @@ pub struct Track {
     pub indexed_ts: i64,
     pub parent_id: Option<String>,
     pub signature: String,
+    pub reference_id: Option<String>,
+    pub edited_ts: Option<i64>,
+    pub signature_version: i32,
+    pub visibility: i32,
+    /// Who besides the author may reply.
+    pub reply_policy: i32,
+}
+
+/// A version of a track from before it was edited.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct Revision {
+    pub track_id: String,
+    pub content: String,
+    pub signature: String,
+    pub ts: i64,
+    pub signature_version: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Verification {
+    pub track_id: String,
+    pub public_key: Option<String>,
+    pub signature_version: i32,
+    pub valid: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -82,14 +142,31 @@ pub struct Thread {
     pub track: Track,
     pub profile: Option<UserProfile>,
     pub likes: i64,
+    /// Every reaction type the track has, with how many of each.
+    pub reactions: Vec<Reaction>,
     pub comments: i64,
     pub bookmarks: i64,
+    pub reposts: i64,
+    pub quotes: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub bookmarked: Option<bool>,
+    /// The reaction types the viewer has applied.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reacted: Option<Vec<i64>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub liked: Option<bool>,
+    pub reposted: Option<bool>,
+    /// Set on ancestors the viewer may not see, which only keep their id and
+    /// parent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hidden: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub children: Option<Vec<Thread>>,
+    /// The tracks this one replies to, starting from the root.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ancestors: Option<Vec<Thread>>,
+    /// The reposted or quoted thread.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<Box<Thread>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -97,6 +174,12 @@ pub struct TokenResult {
     pub actor: Actor,
     pub account: Account,
     pub token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MfaChallenge {
+    pub challenge: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -105,6 +188,8 @@ pub struct Channel {
     pub members: Vec<Actor>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub state: Option<ReadState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unread: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -113,12 +198,14 @@ pub struct Room {
     pub name: Option<String>,
     pub r#type: i32,
     pub last_message_id: Option<String>,
+    pub owner_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RoomMember {
     pub room_id: String,
     pub actor_id: String,
+    pub joined_at: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -129,6 +216,10 @@ pub struct Message {
     pub content: String,
     pub timestamp: i64,
     pub edited_timestamp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -138,3 +229,31 @@ pub struct ReadState {
     pub last_message_id: Option<String>,
     pub mentions: i32,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Device {
+    pub id: String,
+    pub actor_id: String,
+    pub identity_key: String,
+    pub signing_key: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OneTimeKey {
+    pub actor_id: String,
+    pub device_id: String,
+    pub key_id: String,
+    pub key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeviceMessage {
+    pub id: String,
+    pub actor_id: String,
+    pub device_id: String,
+    pub sender_id: String,
+    pub sender_device: String,
+    pub r#type: i32,
+    pub body: String,
+}
@@ -0,0 +1,225 @@
+/*
+   Copyright 2024-2025 V.J. De Chico
+
+   Licensed under the Apache License, Version 2.0 (the "License");
+   you may not use this file except in compliance with the License.
+   You may obtain a copy of the License at
+
+       http://www.apache.org/licenses/LICENSE-2.0
+
+   Unless required by applicable law or agreed to in writing, software
+   distributed under the License is distributed on an "AS IS" BASIS,
+   WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+   See the License for the specific language governing permissions and
+   limitations under the License.
+*/
+
+//! What track signatures are made over, and how to check them.
+//!
+//! Version 1 payloads are the fields below, each on their own line, with
+//! the content last so that it may contain newlines itself:
+//!
+//! ```text
+//! derailed.track.v1
+//! <track id>
+//! <author id>
+//! <timestamp in milliseconds>
+//! <referenced track id, or an empty line>
+//! <content>
+//! ```
+//!
+//! The timestamp is the track's `edited_ts` if it has been edited, and its
+//! `original_ts` otherwise. Version 0 is the original bare concatenation of
+//! the same fields, which is ambiguous and only kept to verify old tracks.
+
+use vodozemac::{Ed25519PublicKey, Ed25519Signature};
+
+use crate::{Revision, Track};
+
+pub const SIGNATURE_VERSION: i32 = 1;
+
+/// Builds the payload for a signature of `version`, if it's a known one.
+pub fn payload(
+    version: i32,
+    id: &str,
+    author_id: &str,
+    ts: i64,
+    reference_id: Option<&str>,
+    content: &str,
+) -> Option<String> {
+    match version {
+        0 => Some(format!(
+            "{}{}{}{}{}",
+            id,
+            author_id,
+            ts,
+            reference_id.unwrap_or_default(),
+            content
+        )),
+        1 => Some(format!(
+            "derailed.track.v1\n{}\n{}\n{}\n{}\n{}",
+            id,
+            author_id,
+            ts,
+            reference_id.unwrap_or_default(),
+            content
+        )),
+        _ => None,
+    }
+}
+
+/// Checks `signature` is `public_key`'s over `payload`, all as base64.
+pub fn verify(public_key: &str, payload: &str, signature: &str) -> bool {
+    let (Ok(key), Ok(signature)) = (
+        Ed25519PublicKey::from_base64(public_key),
+        Ed25519Signature::from_base64(signature),
+    ) else {
+        return false;
+    };
+    key.verify(payload.as_bytes(), &signature).is_ok()
+}
+
+/// Checks the current version of `track` was signed by `public_key`.
+/// Deleted tracks have lost their author and content, so never verify.
+pub fn verify_track(track: &Track, public_key: &str) -> bool {
+    let Some(ref author_id) = track.author_id else {
+        return false;
+    };
+    payload(
+        track.signature_version,
+        &track.id,
+        author_id,
+        track.edited_ts.unwrap_or(track.original_ts),
+        track.reference_id.as_deref(),
+        &track.content,
+    )
+    .is_some_and(|payload| verify(public_key, &payload, &track.signature))
+}
+
+/// Checks an earlier version of `track` was signed by `public_key`.
+pub fn verify_revision(track: &Track, revision: &Revision, public_key: &str) -> bool {
+    let Some(ref author_id) = track.author_id else {
+        return false;
+    };
+    payload(
+        revision.signature_version,
+        &track.id,
+        author_id,
+        revision.ts,
+        track.reference_id.as_deref(),
+        &revision.content,
+    )
+    .is_some_and(|payload| verify(public_key, &payload, &revision.signature))
+}
+
+/// Checks a track arriving from another server before it's stored. Only
+/// the current version is accepted, as version 0 is kept for old tracks.
+pub fn verify_incoming(track: &Track, public_key: &str) -> bool {
+    track.signature_version == SIGNATURE_VERSION && verify_track(track, public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use vodozemac::olm::Account;
+
+    use super::*;
+
+    fn key(acc: &Account) -> String {
+        acc.identity_keys().ed25519.to_base64()
+    }
+
+    fn sign(acc: &Account, version: i32, track: &Track) -> String {
+        let payload = payload(
+            version,
+            &track.id,
+            track.author_id.as_deref().unwrap(),
+            track.edited_ts.unwrap_or(track.original_ts),
+            track.reference_id.as_deref(),
+            &track.content,
+        )
+        .unwrap();
+        acc.sign(payload.as_str()).to_base64()
+    }
+
+    fn track(acc: &Account, version: i32) -> Track {
+        let mut track = Track {
+            id: "1".to_string(),
+            r#type: 0,
+            author_id: Some("2".to_string()),
+            content: "hello\nworld".to_string(),
+            original_ts: 1735689600000,
+            indexed_ts: 1735689600000,
+            parent_id: None,
+            signature: String::new(),
+            reference_id: Some("3".to_string()),
+            edited_ts: None,
+            signature_version: version,
+            visibility: 0,
+            reply_policy: 0,
+        };
+        track.signature = sign(acc, version, &track);
+        track
+    }
+
+    #[test]
+    fn verifies_v1() {
+        let acc = Account::new();
+        let track = track(&acc, 1);
+        assert!(verify_track(&track, &key(&acc)));
+        assert!(verify_incoming(&track, &key(&acc)));
+        assert!(!verify_track(&track, &key(&Account::new())));
+    }
+
+    #[test]
+    fn verifies_legacy_v0() {
+        let acc = Account::new();
+        let track = track(&acc, 0);
+        assert!(verify_track(&track, &key(&acc)));
+        assert!(!verify_incoming(&track, &key(&acc)));
+    }
+
+    #[test]
+    fn verifies_edited() {
+        let acc = Account::new();
+        let mut track = track(&acc, 1);
+        let revision = Revision {
+            track_id: track.id.clone(),
+            content: track.content.clone(),
+            signature: track.signature.clone(),
+            ts: track.original_ts,
+            signature_version: track.signature_version,
+        };
+
+        track.content = "goodbye".to_string();
+        track.edited_ts = Some(track.original_ts + 1000);
+        track.signature = sign(&acc, 1, &track);
+        assert!(verify_track(&track, &key(&acc)));
+        assert!(verify_revision(&track, &revision, &key(&acc)));
+
+        // signed over the original timestamp instead of the edit's
+        track.edited_ts = Some(track.original_ts + 2000);
+        assert!(!verify_track(&track, &key(&acc)));
+    }
+
+    #[test]
+    fn rejects_tampering() {
+        let acc = Account::new();
+        let mut track = track(&acc, 1);
+        track.content = "hello world".to_string();
+        assert!(!verify_track(&track, &key(&acc)));
+
+        let mut track = self::track(&acc, 1);
+        track.reference_id = Some("4".to_string());
+        assert!(!verify_track(&track, &key(&acc)));
+        track.reference_id = None;
+        assert!(!verify_track(&track, &key(&acc)));
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let acc = Account::new();
+        let mut track = track(&acc, 1);
+        track.signature_version = 2;
+        assert!(!verify_track(&track, &key(&acc)));
+    }
+}